@@ -0,0 +1,105 @@
+// Effects drawn over the board when special gems go off. Each one only needs to know where cells are on screen
+// and how far along it is, so they can be drawn over any board.
+
+use crate::theme::{self, glyphs::GlyphSet};
+use cmdjewel_core::point::Point;
+use cursive::theme::ColorStyle;
+use cursive::{Printer, Vec2};
+
+/// How many frames a detonation effect lasts
+pub const EFFECT_LENGTH: u8 = 16;
+
+/// Gets the screen position of the middle of a cell.
+fn center(point: Point<usize>, cell_size: Vec2) -> Vec2 {
+    Vec2::new(
+        point.0 * cell_size.x + cell_size.x / 2,
+        point.1 * cell_size.y + cell_size.y / 2,
+    )
+}
+
+/// Picks a Unicode character, or an ASCII one if that's all the glyph set can use.
+fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if theme::glyphs() == GlyphSet::Unicode {
+        unicode
+    } else {
+        ascii
+    }
+}
+
+/// Sparks flying out of a flame gem into the 3x3 ring around it.
+pub fn burst(
+    printer: &Printer,
+    at: Point<usize>,
+    progress: f32,
+    size: usize,
+    cell: Vec2,
+    color: ColorStyle,
+) {
+    let spark = match (progress * 3.) as usize {
+        0 => glyph("·", "."),
+        1 => glyph("✶", "*"),
+        _ => glyph("✸", "#"),
+    };
+    printer.with_color(color, |printer| {
+        (-1..=1)
+            .flat_map(|y| (-1..=1).map(move |x| (x, y)))
+            .filter(|&(x, y)| x != 0 || y != 0)
+            .filter_map(|(x, y)| {
+                let point = Point(at.0 as i32 + x, at.1 as i32 + y);
+                (point.0 >= 0
+                    && point.1 >= 0
+                    && (point.0 as usize) < size
+                    && (point.1 as usize) < size)
+                    .then_some(Point(point.0 as usize, point.1 as usize))
+            })
+            .for_each(|point| printer.print(center(point, cell), spark));
+    });
+}
+
+/// Beams shooting out of a star gem, across its row and column.
+pub fn beam(
+    printer: &Printer,
+    at: Point<usize>,
+    progress: f32,
+    size: usize,
+    cell: Vec2,
+    color: ColorStyle,
+) {
+    let reach = (progress * size as f32).ceil() as usize;
+    let (horizontal, vertical) = (glyph("━", "-"), glyph("┃", "|"));
+    printer.with_color(color, |printer| {
+        (0..size)
+            .filter(|&i| i != at.0 && i.abs_diff(at.0) <= reach)
+            .for_each(|x| printer.print(center(Point(x, at.1), cell), horizontal));
+        (0..size)
+            .filter(|&i| i != at.1 && i.abs_diff(at.1) <= reach)
+            .for_each(|y| printer.print(center(Point(at.0, y), cell), vertical));
+    });
+}
+
+/// Lightning arcing from a hypercube to every gem it destroys, one after another.
+pub fn lightning(
+    printer: &Printer,
+    at: Point<usize>,
+    targets: &[Point<usize>],
+    progress: f32,
+    cell: Vec2,
+    color: ColorStyle,
+) {
+    let struck = ((progress * targets.len() as f32).ceil() as usize).min(targets.len());
+    let (arc, bolt) = (glyph("·", "."), glyph("ϟ", "%"));
+    printer.with_color(color, |printer| {
+        targets[..struck].iter().for_each(|&target| {
+            // Walk a straight line from the hypercube to the target, in cells
+            let (from, to) = (center(at, cell), center(target, cell));
+            let steps = from.x.abs_diff(to.x).max(from.y.abs_diff(to.y));
+            (1..steps).for_each(|step| {
+                let t = step as f32 / steps as f32;
+                let lerp =
+                    |a: usize, b: usize| (a as f32 + (b as f32 - a as f32) * t).round() as usize;
+                printer.print((lerp(from.x, to.x), lerp(from.y, to.y)), arc);
+            });
+            printer.print(to, bolt);
+        });
+    });
+}
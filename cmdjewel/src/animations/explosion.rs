@@ -1,7 +1,7 @@
 use crate::animations::{Animation, EXPLOSION_GRAVITY};
 use cmdjewel_core::gems::Gem;
 use cmdjewel_core::point::Point;
-use cursive::Printer;
+use cursive::{Printer, Vec2};
 use rand::Rng;
 
 /// Explosion animation.
@@ -64,12 +64,16 @@ impl Animation for Explosion {
         &self,
         printer: &Printer,
         _: &[Gem],
-        width: usize,
+        board_size: Vec2,
         board_offset: &Point<usize>,
     ) {
+        // Around the board, where its border is
         printer.print_box(
-            (board_offset.0, board_offset.1),
-            (width * 3 + 2, width + 2),
+            (
+                board_offset.0.saturating_sub(1),
+                board_offset.1.saturating_sub(1),
+            ),
+            board_size + (2, 2),
             false,
         );
     }
@@ -2,12 +2,16 @@
 pub mod explosion;
 pub mod warp;
 
+/// Effects drawn over the board
+pub mod effects;
+
 use crate::constants;
 use cmdjewel_core::gems::Gem;
-use cmdjewel_core::point::Point;
+use cmdjewel_core::point::{Direction, Point};
 use cursive::event::Event;
 use cursive::event::EventResult;
-use cursive::Printer;
+use cursive::{Printer, Vec2};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 const EXPLOSION_GRAVITY: f32 = 0.04;
@@ -17,8 +21,11 @@ const WARP_KEYFRAMES: usize = 50;
 const WARP_PULL: f32 = 0.2;
 const WARP_SPIN: f32 = 80.;
 
-// Hack to measure the window size of a game
-const WINDOW_SIZE: Point<usize> = Point(39, 13);
+/// Width and height of the area a hint highlights at first
+pub const HINT_REGION_SIZE: usize = 4;
+
+/// How many frames score popups and callouts float over the board for
+pub const POPUP_LENGTH: u8 = 24;
 
 /// Enum containing types of animations (and any data)
 #[derive(PartialEq, Eq)]
@@ -27,6 +34,92 @@ pub enum AnimationType {
     Highlight,
     Explosion,
     Warp,
+    /// Highlights a square of HINT_REGION_SIZE gems (starting at the animation's point) with a move in it
+    HintRegion,
+    /// Points out the exact gem to move and which way to swap it
+    Hint(Direction),
+    /// Moves the gem at the animation's point over from `from`, where it was before a swap
+    Slide {
+        from: Point<usize>,
+        length: u8,
+    },
+    /// Moves the gem at the animation's point halfway to `towards` and back, for swaps that don't work
+    Bounce {
+        towards: Point<usize>,
+        length: u8,
+    },
+    /// Drops the gem at the animation's point in from the cell above it
+    Fall {
+        length: u8,
+    },
+    /// A flame gem exploding into the cells around it
+    Burst,
+    /// A star gem firing across its row and column
+    Beam,
+    /// A hypercube striking every gem it destroys
+    Lightning(Vec<Point<usize>>),
+    /// Text floating up from the animation's point, like points scored. Callouts are drawn bigger and bolder.
+    Popup {
+        text: String,
+        callout: bool,
+    },
+}
+
+impl AnimationType {
+    /// Returns true for hint animations.
+    pub fn is_hint(&self) -> bool {
+        matches!(self, AnimationType::HintRegion | AnimationType::Hint(_))
+    }
+
+    /// Returns true for animations drawn over the board that don't hold it up (hints and popups).
+    pub fn is_overlay(&self) -> bool {
+        self.is_hint() || matches!(self, AnimationType::Popup { .. })
+    }
+
+    /// Returns true for animations that move gems around.
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            AnimationType::Slide { .. } | AnimationType::Bounce { .. } | AnimationType::Fall { .. }
+        )
+    }
+}
+
+/// How fast gems move around the board
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnimationSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    /// Every speed, in the order they're shown in settings
+    pub const ALL: [AnimationSpeed; 3] = [
+        AnimationSpeed::Slow,
+        AnimationSpeed::Normal,
+        AnimationSpeed::Fast,
+    ];
+
+    /// How many frames it takes for a gem to move by one cell
+    pub fn frames(&self) -> u8 {
+        match self {
+            AnimationSpeed::Slow => 6,
+            AnimationSpeed::Normal => 3,
+            AnimationSpeed::Fast => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for AnimationSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimationSpeed::Slow => write!(f, "Slow"),
+            AnimationSpeed::Normal => write!(f, "Normal"),
+            AnimationSpeed::Fast => write!(f, "Fast"),
+        }
+    }
 }
 
 /// Describes an animation
@@ -39,19 +132,61 @@ pub struct AnimationDetails {
     pub animation_type: AnimationType,
 }
 
+impl AnimationDetails {
+    /// Gets where the gem should be drawn, in cells, for animations that move gems around.
+    /// Returns None for other animations.
+    pub fn position(&self) -> Option<Point<f32>> {
+        let point = Point(self.point.0 as f32, self.point.1 as f32);
+        // How far along the animation is, from 0 to 1
+        let progress = |length: u8| 1.0 - self.duration as f32 / length.max(1) as f32;
+        match self.animation_type {
+            AnimationType::Slide { from, length } => {
+                let t = progress(length);
+                Some(Point(
+                    from.0 as f32 + (point.0 - from.0 as f32) * t,
+                    from.1 as f32 + (point.1 - from.1 as f32) * t,
+                ))
+            }
+            AnimationType::Bounce { towards, length } => {
+                // Goes out to halfway, and then back again
+                let t = 0.5 - (progress(length) - 0.5).abs();
+                Some(Point(
+                    point.0 + (towards.0 as f32 - point.0) * t,
+                    point.1 + (towards.1 as f32 - point.1) * t,
+                ))
+            }
+            AnimationType::Fall { length } => {
+                Some(Point(point.0, point.1 - 1.0 + progress(length)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Called once a fullscreen animation is done.
+type OnFinish = Arc<Box<dyn 'static + Fn(&mut cursive::Cursive) + Send + Sync>>;
+
 /// Fullscreen animations.
 pub struct AnimationView<T: Animation + 'static> {
     animation: T,
     data: Vec<Gem>,
+    /// Where the top left of the board is on screen
+    board_offset: Point<usize>,
+    /// How big each cell on the board is
+    cell: Vec2,
     // WTF rust
-    on_finish: Option<Arc<Box<dyn 'static + Fn(&mut cursive::Cursive) + Send + Sync>>>,
+    on_finish: Option<OnFinish>,
 }
 
 impl<T: Animation + 'static> AnimationView<T> {
-    pub fn new(animation: T, data: Vec<Gem>) -> Self {
+    /// Creates a fullscreen animation of a board, given its gems, where it is on screen (see
+    /// `BoardView::screen_offset`), and how big its cells are.
+    pub fn new(animation: T, data: Vec<Gem>, board_offset: Vec2, cell: Vec2) -> Self {
         AnimationView {
             animation,
             data,
+            board_offset: Point(board_offset.x, board_offset.y),
+            cell,
             on_finish: None,
         }
     }
@@ -63,6 +198,8 @@ impl<T: Animation + 'static> AnimationView<T> {
         AnimationView {
             animation: self.animation,
             data: self.data,
+            board_offset: self.board_offset,
+            cell: self.cell,
             on_finish: Some(Arc::new(Box::new(s))),
         }
     }
@@ -75,21 +212,27 @@ impl<T: Animation + 'static> AnimationView<T> {
 
 impl<T: Animation + 'static> cursive::view::View for AnimationView<T> {
     fn draw(&self, printer: &Printer) {
-        // TODO: we can't actually find where the top corner of the board is in global space. maybe one day we can change that.
+        // The board's position is absolute, so take away wherever this view happens to be
         let board_offset = Point(
-            printer.output_size.x / 2 - self.get_width() * 3 / 2 - 1,
-            printer.output_size.y / 2 - self.get_width() / 2 - 1,
+            self.board_offset.0.saturating_sub(printer.offset.x),
+            self.board_offset.1.saturating_sub(printer.offset.y),
         );
         // Draws a background for the animation (if applicable)
-        self.animation
-            .draw_background(printer, &self.data, self.get_width(), &board_offset);
+        self.animation.draw_background(
+            printer,
+            &self.data,
+            self.cell * self.get_width(),
+            &board_offset,
+        );
         // Gets all offsets
         let offsets = self.animation.get_offsets();
         // Loops through/prints NON-EMPTY gems
         for (i, gem) in offsets.iter().enumerate().take(self.data.len()) {
+            // Animations work in small cells, so scale them up to the real ones. Gems sit in the middle of their cells.
             let point = Point(
-                (gem.0 + board_offset.0 as i32) as usize,
-                (gem.1 + board_offset.1 as i32) as usize,
+                (gem.0 * self.cell.x as i32 / 3 + (board_offset.0 + self.cell.x / 2) as i32)
+                    as usize,
+                (gem.1 * self.cell.y as i32 + (board_offset.1 + self.cell.y / 2) as i32) as usize,
             );
             // Prints it
             if self.data[i] != Gem::Empty {
@@ -128,7 +271,8 @@ pub trait Animation: Send + Sync {
     fn get_offsets(&self) -> Vec<Point<i32>>;
     fn get_max_keyframe(&self) -> usize;
     fn get_keyframe(&self) -> usize;
-    fn draw_background(&self, _: &Printer, _: &[Gem], _: usize, _: &Point<usize>);
+    /// Draws behind the gems, given the size of the board on screen and where it is.
+    fn draw_background(&self, _: &Printer, _: &[Gem], _: Vec2, _: &Point<usize>);
     /// Gets the position on screen for each gem, relative to the top left of the board
     fn calculate_positions(num_gems: usize) -> Vec<Point<f32>> {
         let mut positions: Vec<Point<f32>> = Vec::new();
@@ -1,8 +1,9 @@
 use crate::animations::{Animation, WARP_KEYFRAMES, WARP_PULL, WARP_SPIN};
+use crate::theme;
 use cmdjewel_core::gems::Gem;
 use cmdjewel_core::point::Point;
 use cursive::style::{Color, PaletteColor};
-use cursive::Printer;
+use cursive::{Printer, Vec2};
 use rand::Rng;
 
 /// Warp animation.
@@ -45,7 +46,7 @@ impl Warp {
                 if vec.x != 0 && vec.y != 0 {
                     printer.with_color(
                         cursive::theme::ColorStyle::new(color, PaletteColor::Background),
-                        |printer| printer.print(vec, "•"),
+                        |printer| printer.print(vec, theme::glyphs().gem(Gem::Empty)),
                     );
                 }
             });
@@ -99,16 +100,18 @@ impl Animation for Warp {
             .collect();
         if self.keyframe % 15 == 0 && (self.get_max_keyframe() - self.keyframe) > WARP_KEYFRAMES {
             let mut rng = rand::rng();
+            let theme = theme::current();
             let color = match rng.random_range(0..8) {
-                1 => Color::Rgb(126, 158, 189),
-                2 => Color::Rgb(213, 219, 230),
-                3 => Color::Rgb(190, 96, 105),
-                4 => Color::Rgb(233, 201, 138),
-                5 => Color::Rgb(162, 188, 139),
-                6 => Color::Rgb(207, 135, 111),
-                7 => Color::Rgb(174, 174, 255),
-                _ => Color::Rgb(67, 76, 94),
-            };
+                1 => theme.gems.blue,
+                2 => theme.gems.white,
+                3 => theme.gems.red,
+                4 => theme.gems.yellow,
+                5 => theme.gems.green,
+                6 => theme.gems.orange,
+                7 => theme.gems.purple,
+                _ => theme.muted,
+            }
+            .color();
             self.circles.push((0, color))
         }
         if self.circles.len() > 5 {
@@ -132,14 +135,20 @@ impl Animation for Warp {
         self.keyframe
     }
 
-    fn draw_background(&self, printer: &Printer, _: &[Gem], _: usize, _: &Point<usize>) {
+    fn draw_background(
+        &self,
+        printer: &Printer,
+        _: &[Gem],
+        board_size: Vec2,
+        board_offset: &Point<usize>,
+    ) {
+        // Circles ripple out from the middle of the board
+        let center = Point(
+            (board_offset.0 + board_size.x / 2) as f32,
+            (board_offset.1 + board_size.y / 2) as f32,
+        );
         self.circles.iter().for_each(|circle| {
-            Warp::draw_circle(
-                printer,
-                Point((printer.size.x / 2) as f32, (printer.size.y / 2) as f32),
-                circle.0 as i32,
-                circle.1,
-            );
+            Warp::draw_circle(printer, center, circle.0 as i32, circle.1);
         })
     }
 }
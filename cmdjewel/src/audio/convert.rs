@@ -0,0 +1,916 @@
+// Converting FastTracker II (.xm) and ProTracker (.mod) modules into Impulse Tracker ones, since that's all it2play
+// can play. Effects that IT doesn't have an equivalent for are dropped, so some songs won't sound exactly the same.
+
+use super::module;
+use crate::constants::strings;
+
+/// Most of each kind of thing it2play will load
+const MAX_SAMPLES: usize = 100;
+const MAX_INSTRUMENTS: usize = 99;
+const MAX_PATTERNS: usize = 200;
+const MAX_ROWS: usize = 200;
+const MAX_CHANNELS: usize = 64;
+
+/// Order list markers
+const ORDER_SKIP: u8 = 254;
+const ORDER_END: u8 = 255;
+
+/// IT notes that aren't notes
+const NOTE_OFF: u8 = 255;
+
+/// IT effects, numbered from A = 1
+const CMD_SPEED: u8 = 1; // A
+const CMD_JUMP: u8 = 2; // B
+const CMD_BREAK: u8 = 3; // C
+const CMD_VOLUME_SLIDE: u8 = 4; // D
+const CMD_PORTA_DOWN: u8 = 5; // E
+const CMD_PORTA_UP: u8 = 6; // F
+const CMD_TONE_PORTA: u8 = 7; // G
+const CMD_VIBRATO: u8 = 8; // H
+const CMD_TREMOR: u8 = 9; // I
+const CMD_ARPEGGIO: u8 = 10; // J
+const CMD_VIBRATO_VOLUME: u8 = 11; // K
+const CMD_PORTA_VOLUME: u8 = 12; // L
+const CMD_OFFSET: u8 = 15; // O
+const CMD_PAN_SLIDE: u8 = 16; // P
+const CMD_RETRIGGER: u8 = 17; // Q
+const CMD_TREMOLO: u8 = 18; // R
+const CMD_SPECIAL: u8 = 19; // S
+const CMD_TEMPO: u8 = 20; // T
+const CMD_GLOBAL_VOLUME: u8 = 22; // V
+const CMD_GLOBAL_VOLUME_SLIDE: u8 = 23; // W
+const CMD_PAN: u8 = 24; // X
+
+/// Tone portamento speeds the IT volume column can hold
+const VOLUME_PORTA_SPEEDS: [u8; 10] = [0x00, 0x01, 0x04, 0x08, 0x10, 0x20, 0x40, 0x60, 0x80, 0xFF];
+
+/// Converts a ProTracker module to an Impulse Tracker one.
+pub fn from_protracker(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    read_protracker(bytes)
+        .ok_or(strings::MODULE_BROKEN)?
+        .write()
+}
+
+/// Converts a FastTracker II module to an Impulse Tracker one.
+pub fn from_fasttracker(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    read_fasttracker(bytes)
+        .ok_or(strings::MODULE_BROKEN)?
+        .write()
+}
+
+/// Reads a ProTracker module into an Impulse Tracker one. Returns None if the module is cut short.
+fn read_protracker(bytes: &[u8]) -> Option<ItModule> {
+    let channels = module::protracker_channels(bytes)?;
+    // Startrekker's 8 channel modules store each pattern as two 4 channel halves
+    let halves = &bytes[1080..1084] == b"FLT8";
+    let stored_channels = if halves { 4 } else { channels };
+    let song_length = (*bytes.get(950)?).clamp(1, 128) as usize;
+    let mut orders = bytes.get(952..952 + 128)?.to_vec();
+    if halves {
+        orders.iter_mut().for_each(|order| *order /= 2);
+    }
+    // Every order counts towards the number of patterns, even ones past the end of the song
+    let num_patterns = *orders.iter().max()? as usize + 1;
+    let stored_size = 64 * stored_channels * 4;
+    let sample_start = 1084 + num_patterns * stored_size * if halves { 2 } else { 1 };
+
+    let mut it = ItModule::new(bytes.get(..20)?, channels);
+    it.flags = IT_STEREO | IT_OLD_EFFECTS;
+    // Amiga panning: left, right, right, left
+    it.channels
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, (pan, _))| *pan = if i % 4 == 0 || i % 4 == 3 { 16 } else { 48 });
+    it.orders = orders[..song_length].to_vec();
+
+    // Patterns
+    for pattern in 0..num_patterns.min(MAX_PATTERNS) {
+        let mut rows = vec![vec![Cell::default(); channels]; 64];
+        for (row, cells) in rows.iter_mut().enumerate() {
+            for (channel, cell) in cells.iter_mut().enumerate() {
+                let (stored, stored_channel) = if halves {
+                    (pattern * 2 + channel / 4, channel % 4)
+                } else {
+                    (pattern, channel)
+                };
+                let at = 1084 + stored * stored_size + (row * stored_channels + stored_channel) * 4;
+                let data = bytes.get(at..at + 4)?;
+                let period = ((data[0] as u16 & 0x0F) << 8) | data[1] as u16;
+                if period > 0 {
+                    // Period 428 is C-2 in ProTracker, which plays at the same speed as C-5 in IT
+                    let note = 60.0 + 12.0 * (428.0 / period as f32).log2();
+                    cell.note = Some(note.round().clamp(0.0, 119.0) as u8);
+                }
+                cell.instrument = (data[0] & 0xF0) | (data[2] >> 4);
+                convert_effect(cell, data[2] & 0x0F, data[3], false);
+            }
+        }
+        it.patterns.push(rows);
+    }
+
+    // Samples
+    let mut offset = sample_start;
+    for i in 0..31 {
+        let header = bytes.get(20 + i * 30..20 + (i + 1) * 30)?;
+        let length = u16::from_be_bytes([header[22], header[23]]) as usize * 2;
+        let finetune = ((header[24] & 0x0F) << 4) as i8 >> 4;
+        let loop_start = u16::from_be_bytes([header[26], header[27]]) as usize * 2;
+        let loop_length = u16::from_be_bytes([header[28], header[29]]) as usize * 2;
+        // The last sample is often cut short, so take what's there
+        let data = bytes.get(offset.min(bytes.len())..(offset + length).min(bytes.len()))?;
+        offset += length;
+        let loop_end = (loop_start + loop_length).min(data.len());
+        it.samples.push(ItSample {
+            name: header[..22].to_vec(),
+            volume: header[25].min(64),
+            pan: None,
+            c5speed: (8363.0 * 2f32.powf(finetune as f32 / 96.0)) as u32,
+            looping: (loop_length > 2 && loop_start < loop_end).then_some((
+                loop_start as u32,
+                loop_end as u32,
+                false,
+            )),
+            vibrato: [0; 4],
+            data: SampleData::Bits8(data.iter().map(|&x| x as i8).collect()),
+        });
+    }
+    Some(it)
+}
+
+/// Reads a FastTracker II module into an Impulse Tracker one. Returns None if the module is cut short.
+fn read_fasttracker(bytes: &[u8]) -> Option<ItModule> {
+    let header_size = u32_le(bytes, 60)? as usize;
+    let song_length = (u16_le(bytes, 64)? as usize).clamp(1, 256);
+    let channels = (u16_le(bytes, 68)? as usize).clamp(1, MAX_CHANNELS);
+    let num_patterns = u16_le(bytes, 70)? as usize;
+    let num_instruments = u16_le(bytes, 72)? as usize;
+    let linear = u16_le(bytes, 74)? & 1 == 1;
+
+    let mut it = ItModule::new(bytes.get(17..37)?, channels);
+    it.speed = u16_le(bytes, 76)?.clamp(1, 255) as u8;
+    it.tempo = u16_le(bytes, 78)?.clamp(32, 255) as u8;
+    it.flags = IT_STEREO | IT_INSTRUMENTS | IT_OLD_EFFECTS | if linear { IT_LINEAR } else { 0 };
+
+    // Patterns
+    let mut offset = 60 + header_size;
+    for _ in 0..num_patterns {
+        let length = u32_le(bytes, offset)? as usize;
+        let num_rows = (u16_le(bytes, offset + 5)? as usize).max(1);
+        let packed_size = u16_le(bytes, offset + 7)? as usize;
+        let mut data = bytes
+            .get(offset + length..offset + length + packed_size)?
+            .iter();
+        offset += length + packed_size;
+        // IT can't have patterns as long as XM can, so anything past that is cut off
+        let mut rows = vec![vec![Cell::default(); channels]; num_rows.min(MAX_ROWS)];
+        for row in 0..num_rows {
+            for channel in 0..channels {
+                if data.len() == 0 {
+                    break;
+                }
+                let first = *data.next()?;
+                let (mask, note) = if first & 0x80 != 0 {
+                    (first, None)
+                } else {
+                    (0xFF, Some(first))
+                };
+                let mut next = |bit: u8| match mask & bit {
+                    0 => Some(0),
+                    _ => data.next().copied(),
+                };
+                let note = match note {
+                    Some(note) => note,
+                    None => next(0x01)?,
+                };
+                let instrument = next(0x02)?;
+                let volume = next(0x04)?;
+                let effect = next(0x08)?;
+                let param = next(0x10)?;
+                let Some(cell) = rows.get_mut(row).map(|cells| &mut cells[channel]) else {
+                    continue;
+                };
+                cell.note = match note {
+                    1..=96 => Some(note - 1 + 12),
+                    97 => Some(NOTE_OFF),
+                    _ => None,
+                };
+                cell.instrument = if (instrument as usize) <= MAX_INSTRUMENTS {
+                    instrument
+                } else {
+                    0
+                };
+                convert_volume(cell, volume);
+                convert_effect(cell, effect, param, true);
+            }
+        }
+        it.patterns.push(rows);
+    }
+    // XM patterns can be referenced without being saved, in which case they're empty
+    let orders = bytes.get(80..80 + song_length)?;
+    if orders
+        .iter()
+        .any(|&order| order as usize >= it.patterns.len())
+    {
+        it.patterns.push(vec![vec![Cell::default(); channels]; 64]);
+    }
+    let empty = it.patterns.len() - 1;
+    it.orders = orders
+        .iter()
+        .map(|&order| match order as usize {
+            order if order < it.patterns.len().min(MAX_PATTERNS) => order as u8,
+            _ if empty < MAX_PATTERNS => empty as u8,
+            _ => ORDER_SKIP,
+        })
+        .collect();
+    it.patterns.truncate(MAX_PATTERNS);
+
+    // Instruments, and the samples in each of them
+    for _ in 0..num_instruments {
+        let size = u32_le(bytes, offset)? as usize;
+        // Some trackers save shorter headers when there aren't any samples, so pad them out
+        let mut header = [0u8; 263];
+        let available = bytes.get(offset..offset + size.min(263))?;
+        header[..available.len()].copy_from_slice(available);
+        let num_samples = u16_le(&header, 27)? as usize;
+        offset += size;
+
+        let first_sample = it.samples.len();
+        let mut headers = Vec::new();
+        for _ in 0..num_samples {
+            headers.push(bytes.get(offset..offset + 40)?);
+            offset += 40;
+        }
+        for sample in headers {
+            let length = u32_le(sample, 0)? as usize;
+            let bits16 = sample[14] & 0x10 != 0;
+            let adpcm = sample[17] == 0xAD;
+            let stored = if adpcm {
+                16 + length.div_ceil(2)
+            } else {
+                length
+            };
+            let data = bytes.get(offset.min(bytes.len())..(offset + stored).min(bytes.len()))?;
+            offset += stored;
+            // Everything in the sample header is counted in bytes, rather than in samples
+            let width = if bits16 { 2 } else { 1 };
+            let loop_start = u32_le(sample, 4)? / width;
+            let loop_end = loop_start.saturating_add(u32_le(sample, 8)? / width);
+            let pitch = sample[16] as i8 as f32 + sample[13] as i8 as f32 / 128.0;
+            let vibrato_depth = header[237];
+            let vibrato_sweep = header[236] as u32;
+            it.samples.push(ItSample {
+                name: sample[18..40].to_vec(),
+                volume: sample[12].min(64),
+                pan: Some((sample[15] as u32 * 64 / 255) as u8),
+                c5speed: (8363.0 * 2f32.powf(pitch / 12.0)) as u32,
+                looping: (sample[14] & 0x03 != 0 && loop_end > loop_start).then_some((
+                    loop_start,
+                    loop_end,
+                    sample[14] & 0x03 == 2,
+                )),
+                // Speed, depth, how fast the depth is reached, and the waveform (IT has square and ramp swapped)
+                vibrato: [
+                    header[238],
+                    vibrato_depth,
+                    match vibrato_sweep {
+                        0 => 255,
+                        sweep => (vibrato_depth as u32 * 256 / sweep).clamp(1, 255) as u8,
+                    },
+                    [0, 2, 1, 1][header[235] as usize & 3],
+                ],
+                // ModPlug's ADPCM samples aren't worth decoding, so they're left silent
+                data: match (adpcm, bits16) {
+                    (true, _) => SampleData::Bits8(Vec::new()),
+                    (false, false) => SampleData::Bits8(
+                        data.iter()
+                            .scan(0i8, |last, &delta| {
+                                *last = last.wrapping_add(delta as i8);
+                                Some(*last)
+                            })
+                            .collect(),
+                    ),
+                    (false, true) => SampleData::Bits16(
+                        data.chunks_exact(2)
+                            .scan(0i16, |last, pair| {
+                                *last = last.wrapping_add(i16::from_le_bytes([pair[0], pair[1]]));
+                                Some(*last)
+                            })
+                            .collect(),
+                    ),
+                },
+            });
+        }
+
+        // Maps every IT note to a sample, shifting up an octave to line up with the notes in the patterns
+        let mut keyboard = [(0u8, 0u8); 120];
+        keyboard.iter_mut().enumerate().for_each(|(note, key)| {
+            let xm_note = note.saturating_sub(12).min(95);
+            let sample = header[33 + xm_note] as usize;
+            let number = first_sample + sample + 1;
+            *key = (
+                note as u8,
+                if sample < num_samples && number <= MAX_SAMPLES {
+                    number as u8
+                } else {
+                    0
+                },
+            );
+        });
+        let volume_envelope = Envelope::from_xm(&header, 129, 225, 227, 233, 0);
+        let pan_envelope = Envelope::from_xm(&header, 177, 226, 230, 234, 32);
+        it.instruments.push(ItInstrument {
+            name: header[4..26].to_vec(),
+            fadeout: u16_le(&header, 239)? / 32,
+            keyboard,
+            // Without a volume envelope, XM cuts notes as soon as they're let go
+            volume_envelope: volume_envelope.unwrap_or(Envelope {
+                nodes: vec![(64, 0), (0, 1)],
+                looping: None,
+                sustain: Some(0),
+            }),
+            pan_envelope,
+        });
+    }
+    it.instruments.truncate(MAX_INSTRUMENTS);
+    it.samples.truncate(MAX_SAMPLES);
+    Some(it)
+}
+
+/// Converts an XM volume column byte, which can also hold a few effects.
+fn convert_volume(cell: &mut Cell, volume: u8) {
+    let x = volume & 0x0F;
+    cell.volume = match volume {
+        0x10..=0x50 => Some(volume - 0x10),
+        0x60..=0x6F => Some(95 + x.min(9)),
+        0x70..=0x7F => Some(85 + x.min(9)),
+        0x80..=0x8F => Some(75 + x.min(9)),
+        0x90..=0x9F => Some(65 + x.min(9)),
+        0xB0..=0xBF => Some(203 + x.min(9)),
+        0xC0..=0xCF => Some(128 + (x as u16 * 64 / 15) as u8),
+        0xF0..=0xFF => {
+            let speed = x * 16;
+            let index = VOLUME_PORTA_SPEEDS
+                .iter()
+                .position(|&s| s >= speed)
+                .unwrap_or(9);
+            Some(193 + index as u8)
+        }
+        // Panning slides don't fit in IT's volume column, so they go in the effect column instead
+        0xD0..=0xDF => {
+            (cell.command, cell.param) = (CMD_PAN_SLIDE, x << 4);
+            None
+        }
+        0xE0..=0xEF => {
+            (cell.command, cell.param) = (CMD_PAN_SLIDE, x);
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Converts a ProTracker or FastTracker effect (which are the same up to F) into an IT one.
+/// ProTracker doesn't remember the last value of slides, so those are dropped when they're zero.
+fn convert_effect(cell: &mut Cell, effect: u8, param: u8, xm: bool) {
+    let (hi, lo) = (param >> 4, param & 0x0F);
+    // Slides that go one way have the other half of the parameter emptied out
+    let slide = if hi > 0 { param & 0xF0 } else { lo };
+    let remembers = xm || param != 0;
+    let (command, param) = match effect {
+        0x0 if param != 0 => (CMD_ARPEGGIO, param),
+        0x1 if remembers => (CMD_PORTA_UP, param.min(0xDF)),
+        0x2 if remembers => (CMD_PORTA_DOWN, param.min(0xDF)),
+        0x3 => (CMD_TONE_PORTA, param),
+        0x4 => (CMD_VIBRATO, param),
+        0x5 => (CMD_PORTA_VOLUME, slide),
+        0x6 => (CMD_VIBRATO_VOLUME, slide),
+        0x7 => (CMD_TREMOLO, param),
+        0x8 => (CMD_PAN, param),
+        0x9 => (CMD_OFFSET, param),
+        0xA if remembers => (CMD_VOLUME_SLIDE, slide),
+        0xB => (CMD_JUMP, param),
+        0xC => {
+            cell.volume = Some(param.min(64));
+            return;
+        }
+        // Pattern breaks are written in decimal
+        0xD => (CMD_BREAK, hi * 10 + lo),
+        0xE => match hi {
+            0x1 if lo > 0 => (CMD_PORTA_UP, 0xF0 | lo),
+            0x2 if lo > 0 => (CMD_PORTA_DOWN, 0xF0 | lo),
+            0x3 => (CMD_SPECIAL, 0x10 | lo),
+            0x4 => (CMD_SPECIAL, 0x30 | lo),
+            0x6 => (CMD_SPECIAL, 0xB0 | lo),
+            0x7 => (CMD_SPECIAL, 0x40 | lo),
+            0x8 => (CMD_SPECIAL, 0x80 | lo),
+            0x9 => (CMD_RETRIGGER, lo),
+            0xA if lo > 0 => (CMD_VOLUME_SLIDE, (lo << 4) | 0x0F),
+            0xB if lo > 0 => (CMD_VOLUME_SLIDE, 0xF0 | lo),
+            0xC => (CMD_SPECIAL, 0xC0 | lo),
+            0xD => (CMD_SPECIAL, 0xD0 | lo),
+            0xE => (CMD_SPECIAL, 0xE0 | lo),
+            _ => return,
+        },
+        // F00 stops the song, which IT can't do
+        0xF if param == 0 => return,
+        0xF if param < 0x20 => (CMD_SPEED, param),
+        0xF => (CMD_TEMPO, param),
+        // Everything past here is only in FastTracker
+        0x10 if xm => (CMD_GLOBAL_VOLUME, param.min(64) * 2),
+        0x11 if xm => (CMD_GLOBAL_VOLUME_SLIDE, slide),
+        // Key off, possibly a few ticks into the row
+        0x14 if xm && cell.note.is_none() => {
+            cell.note = Some(NOTE_OFF);
+            match param {
+                0 => return,
+                ticks => (CMD_SPECIAL, 0xD0 | ticks.min(15)),
+            }
+        }
+        // XM slides panning right with the high half, IT with the low half
+        0x19 if xm => (CMD_PAN_SLIDE, (lo << 4) | hi),
+        0x1B if xm => (CMD_RETRIGGER, param),
+        0x1D if xm => (CMD_TREMOR, param),
+        0x21 if xm => match hi {
+            0x1 if lo > 0 => (CMD_PORTA_UP, 0xE0 | lo),
+            0x2 if lo > 0 => (CMD_PORTA_DOWN, 0xE0 | lo),
+            _ => return,
+        },
+        _ => return,
+    };
+    (cell.command, cell.param) = (command, param);
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// IT header flags
+const IT_STEREO: u16 = 1;
+const IT_INSTRUMENTS: u16 = 4;
+const IT_LINEAR: u16 = 8;
+const IT_OLD_EFFECTS: u16 = 16;
+
+/// One channel on one row of a pattern
+#[derive(Clone, Default)]
+struct Cell {
+    note: Option<u8>,
+    /// 0 for none
+    instrument: u8,
+    /// Volume or panning, or one of the effects that fit in the volume column
+    volume: Option<u8>,
+    /// 0 for none
+    command: u8,
+    param: u8,
+}
+
+enum SampleData {
+    Bits8(Vec<i8>),
+    Bits16(Vec<i16>),
+}
+
+struct ItSample {
+    name: Vec<u8>,
+    volume: u8,
+    /// 0 to 64, if the sample sets the panning
+    pan: Option<u8>,
+    c5speed: u32,
+    /// Start, end, and whether it goes back and forth
+    looping: Option<(u32, u32, bool)>,
+    /// Speed, depth, rate, and waveform
+    vibrato: [u8; 4],
+    data: SampleData,
+}
+
+struct Envelope {
+    /// (value, tick) pairs
+    nodes: Vec<(i8, u16)>,
+    /// Start and end nodes
+    looping: Option<(u8, u8)>,
+    /// Node to hold on until the note's let go
+    sustain: Option<u8>,
+}
+
+impl Envelope {
+    /// Reads an XM envelope from an instrument header, given where each part of it is. Values are moved down by
+    /// `center` (so panning envelopes go from -32 to 32). Returns None if the envelope's turned off.
+    fn from_xm(
+        header: &[u8],
+        points: usize,
+        count: usize,
+        sustain: usize,
+        flags: usize,
+        center: i16,
+    ) -> Option<Envelope> {
+        let num = (header[count] as usize).min(12);
+        let flags = header[flags];
+        if flags & 1 == 0 || num < 2 {
+            return None;
+        }
+        let nodes = (0..num)
+            .map(|i| {
+                let tick = u16_le(header, points + i * 4).unwrap_or(0);
+                let value = u16_le(header, points + i * 4 + 2).unwrap_or(0) as i16;
+                ((value.min(64) - center) as i8, tick)
+            })
+            .collect();
+        let last = num as u8 - 1;
+        Some(Envelope {
+            nodes,
+            looping: (flags & 4 != 0)
+                .then(|| (header[sustain + 1].min(last), header[sustain + 2].min(last))),
+            sustain: (flags & 2 != 0).then(|| header[sustain].min(last)),
+        })
+    }
+
+    fn write(envelope: Option<&Envelope>, out: &mut Vec<u8>) {
+        let start = out.len();
+        if let Some(envelope) = envelope {
+            let (loop_start, loop_end) = envelope.looping.unwrap_or((0, 0));
+            let sustain = envelope.sustain.unwrap_or(0);
+            out.push(
+                1 | (envelope.looping.is_some() as u8) << 1
+                    | (envelope.sustain.is_some() as u8) << 2,
+            );
+            out.extend([
+                envelope.nodes.len() as u8,
+                loop_start,
+                loop_end,
+                sustain,
+                sustain,
+            ]);
+            envelope.nodes.iter().for_each(|&(value, tick)| {
+                out.push(value as u8);
+                out.extend(tick.to_le_bytes());
+            });
+        }
+        out.resize(start + 82, 0);
+    }
+}
+
+struct ItInstrument {
+    name: Vec<u8>,
+    fadeout: u16,
+    /// (note, sample) for every note
+    keyboard: [(u8, u8); 120],
+    volume_envelope: Envelope,
+    pan_envelope: Option<Envelope>,
+}
+
+/// An Impulse Tracker module, ready to be written out
+struct ItModule {
+    name: Vec<u8>,
+    flags: u16,
+    speed: u8,
+    tempo: u8,
+    /// Panning and volume for every channel that's used
+    channels: Vec<(u8, u8)>,
+    orders: Vec<u8>,
+    instruments: Vec<ItInstrument>,
+    samples: Vec<ItSample>,
+    /// Rows of cells, one per channel
+    patterns: Vec<Vec<Vec<Cell>>>,
+}
+
+impl ItModule {
+    fn new(name: &[u8], channels: usize) -> Self {
+        ItModule {
+            name: name.to_vec(),
+            flags: IT_STEREO,
+            speed: 6,
+            tempo: 125,
+            channels: vec![(32, 64); channels.min(MAX_CHANNELS)],
+            orders: Vec::new(),
+            instruments: Vec::new(),
+            samples: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Writes the module out in the same layout Impulse Tracker saves in. Fails if a pattern packs down to more than
+    /// IT can say the size of.
+    fn write(&self) -> Result<Vec<u8>, String> {
+        let num_orders = self.orders.len().min(255) + 1;
+        let mut out = Vec::new();
+        out.extend(b"IMPM");
+        push_text(&mut out, &self.name, 26);
+        out.extend([0x04, 0x10]); // Row highlights
+        out.extend((num_orders as u16).to_le_bytes());
+        out.extend((self.instruments.len() as u16).to_le_bytes());
+        out.extend((self.samples.len() as u16).to_le_bytes());
+        out.extend((self.patterns.len() as u16).to_le_bytes());
+        out.extend(0x0214u16.to_le_bytes()); // Made with IT 2.14...
+        out.extend(0x0214u16.to_le_bytes()); // ...and needs 2.14 to play
+        out.extend(self.flags.to_le_bytes());
+        out.extend(0u16.to_le_bytes()); // No message
+        out.extend([128, 48, self.speed, self.tempo, 128, 0]);
+        out.extend([0; 2 + 4 + 4]); // Message length, offset, and reserved
+        (0..MAX_CHANNELS).for_each(|i| {
+            // Channels that aren't used are turned off
+            out.push(self.channels.get(i).map_or(32 | 128, |&(pan, _)| pan));
+        });
+        (0..MAX_CHANNELS)
+            .for_each(|i| out.push(self.channels.get(i).map_or(64, |&(_, volume)| volume)));
+        out.extend(&self.orders[..num_orders - 1]);
+        out.push(ORDER_END);
+
+        // Offsets to everything get filled in as it's written
+        let pointers = out.len();
+        let num_pointers = self.instruments.len() + self.samples.len() + self.patterns.len();
+        out.resize(pointers + num_pointers * 4, 0);
+        let set_pointer = |out: &mut Vec<u8>, index: usize| {
+            let offset = (out.len() as u32).to_le_bytes();
+            out[pointers + index * 4..pointers + index * 4 + 4].copy_from_slice(&offset);
+        };
+
+        for (i, instrument) in self.instruments.iter().enumerate() {
+            set_pointer(&mut out, i);
+            out.extend(b"IMPI");
+            out.extend([0; 13]); // File name
+            out.extend([0, 0, 0]); // Note cut, no duplicate checks
+            out.extend(instrument.fadeout.to_le_bytes());
+            out.extend([0, 60, 128, 32 | 128, 0, 0]); // Pitch-pan, global volume, no default panning, no randomness
+            out.extend([0; 4]); // Tracker version, sample count
+            push_text(&mut out, &instrument.name, 26);
+            out.extend([0, 0, 0, 0, 0, 0]); // Filter and MIDI
+            instrument
+                .keyboard
+                .iter()
+                .for_each(|&(note, sample)| out.extend([note, sample]));
+            Envelope::write(Some(&instrument.volume_envelope), &mut out);
+            Envelope::write(instrument.pan_envelope.as_ref(), &mut out);
+            Envelope::write(None, &mut out);
+            out.extend([0; 4]);
+        }
+
+        let mut sample_headers = Vec::new();
+        for (i, sample) in self.samples.iter().enumerate() {
+            set_pointer(&mut out, self.instruments.len() + i);
+            sample_headers.push(out.len());
+            let (length, bits16) = match &sample.data {
+                SampleData::Bits8(data) => (data.len(), false),
+                SampleData::Bits16(data) => (data.len(), true),
+            };
+            let (loop_start, loop_end, pingpong) = sample.looping.unwrap_or_default();
+            let loop_end = loop_end.min(length as u32);
+            let looping = sample.looping.is_some() && loop_start < loop_end;
+            out.extend(b"IMPS");
+            out.extend([0; 13]); // File name
+            out.push(64); // Global volume
+            out.push(
+                (length > 0) as u8
+                    | ((bits16 as u8) << 1)
+                    | ((looping as u8) << 4)
+                    | ((looping && pingpong) as u8 * 0x40),
+            );
+            out.push(sample.volume);
+            push_text(&mut out, &sample.name, 26);
+            out.push(1); // Signed
+            out.push(sample.pan.map_or(32, |pan| pan.min(64) | 128));
+            out.extend((length as u32).to_le_bytes());
+            out.extend(loop_start.to_le_bytes());
+            out.extend(loop_end.to_le_bytes());
+            out.extend(sample.c5speed.to_le_bytes());
+            out.extend([0; 8]); // No sustain loop
+            out.extend([0; 4]); // Where the sample data is, filled in below
+            out.extend(sample.vibrato);
+        }
+
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            set_pointer(&mut out, self.instruments.len() + self.samples.len() + i);
+            let mut packed = Vec::new();
+            for row in pattern {
+                for (channel, cell) in row.iter().enumerate() {
+                    let mask = cell.note.is_some() as u8
+                        | (((cell.instrument != 0) as u8) << 1)
+                        | ((cell.volume.is_some() as u8) << 2)
+                        | (((cell.command != 0) as u8) << 3);
+                    if mask == 0 {
+                        continue;
+                    }
+                    packed.extend([(channel as u8 + 1) | 0x80, mask]);
+                    packed.extend(cell.note);
+                    if cell.instrument != 0 {
+                        packed.push(cell.instrument);
+                    }
+                    packed.extend(cell.volume);
+                    if cell.command != 0 {
+                        packed.extend([cell.command, cell.param]);
+                    }
+                }
+                packed.push(0); // End of row
+            }
+            let size = u16::try_from(packed.len()).map_err(|_| strings::pattern_too_big(i))?;
+            out.extend(size.to_le_bytes());
+            out.extend((pattern.len() as u16).to_le_bytes());
+            out.extend([0; 4]);
+            out.extend(packed);
+        }
+
+        for (sample, header) in self.samples.iter().zip(sample_headers) {
+            let offset = (out.len() as u32).to_le_bytes();
+            out[header + 0x48..header + 0x4C].copy_from_slice(&offset);
+            match &sample.data {
+                SampleData::Bits8(data) => out.extend(data.iter().map(|&x| x as u8)),
+                SampleData::Bits16(data) => data.iter().for_each(|x| out.extend(x.to_le_bytes())),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Writes text padded (or cut) to a given length, always leaving room for a null at the end.
+fn push_text(out: &mut Vec<u8>, text: &[u8], length: usize) {
+    let text = &text[..text.len().min(length - 1)];
+    out.extend(text);
+    out.resize(out.len() + length - text.len(), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a converted module's header says is in it, and where
+    struct Header {
+        flags: u16,
+        orders: Vec<u8>,
+        instruments: Vec<usize>,
+        samples: Vec<usize>,
+        patterns: Vec<usize>,
+    }
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16_le(bytes, at).unwrap() as usize
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> usize {
+        u32_le(bytes, at).unwrap() as usize
+    }
+
+    /// Reads an IT header, checking every offset in it points at the right kind of thing.
+    fn read_header(it: &[u8]) -> Header {
+        assert_eq!(&it[..4], b"IMPM");
+        let num_orders = u16_at(it, 0x20);
+        let counts = [u16_at(it, 0x22), u16_at(it, 0x24), u16_at(it, 0x26)];
+        let mut pointers = (0..counts.iter().sum::<usize>())
+            .map(|i| u32_at(it, 0xC0 + num_orders + i * 4))
+            .inspect(|&p| assert!(p < it.len(), "pointer past the end of the module"));
+        let mut take = |n: usize| pointers.by_ref().take(n).collect::<Vec<_>>();
+        let header = Header {
+            flags: u16_at(it, 0x2C) as u16,
+            orders: it[0xC0..0xC0 + num_orders].to_vec(),
+            instruments: take(counts[0]),
+            samples: take(counts[1]),
+            patterns: take(counts[2]),
+        };
+        header
+            .instruments
+            .iter()
+            .for_each(|&p| assert_eq!(&it[p..p + 4], b"IMPI"));
+        header
+            .samples
+            .iter()
+            .for_each(|&p| assert_eq!(&it[p..p + 4], b"IMPS"));
+        header
+    }
+
+    /// Gets a sample's data out of a converted module, as bytes.
+    fn sample_data(it: &[u8], sample: usize) -> &[u8] {
+        let width = if it[sample + 0x12] & 2 != 0 { 2 } else { 1 };
+        let start = u32_at(it, sample + 0x48);
+        &it[start..start + u32_at(it, sample + 0x30) * width]
+    }
+
+    /// Gets a pattern's row count and packed data out of a converted module.
+    fn pattern(it: &[u8], pattern: usize) -> (usize, &[u8]) {
+        let length = u16_at(it, pattern);
+        (
+            u16_at(it, pattern + 2),
+            &it[pattern + 8..pattern + 8 + length],
+        )
+    }
+
+    /// A 4 channel ProTracker module with one pattern, which plays a C on the first row at half volume, and one
+    /// sample.
+    fn protracker() -> Vec<u8> {
+        let mut bytes = vec![0; 1084 + 64 * 4 * 4];
+        bytes[..4].copy_from_slice(b"test");
+        // Sample 1: 32 bytes long, at full volume
+        bytes[20..24].copy_from_slice(b"lead");
+        bytes[42..44].copy_from_slice(&16u16.to_be_bytes());
+        bytes[45] = 64;
+        bytes[950] = 1;
+        bytes[1080..1084].copy_from_slice(b"M.K.");
+        // Period 428 (C-2), sample 1, C20 (volume 32)
+        bytes[1084..1088].copy_from_slice(&[0x01, 0xAC, 0x1C, 0x20]);
+        bytes.extend((0..32).map(|i| i * 4));
+        bytes
+    }
+
+    /// A 2 channel FastTracker module with one instrument and a two row pattern: a C at full volume, then a key off.
+    fn fasttracker() -> Vec<u8> {
+        let mut bytes = b"Extended Module: test".to_vec();
+        bytes.resize(60, 0);
+        bytes[37] = 0x1A;
+        bytes[58..60].copy_from_slice(&0x0104u16.to_le_bytes());
+        bytes.extend(276u32.to_le_bytes()); // Header size
+                                            // Song length, restart, channels, patterns, instruments, flags, speed, and tempo
+        for value in [1u16, 0, 2, 1, 1, 1, 6, 125] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.resize(336, 0);
+        // The pattern
+        let packed = [49, 1, 0x50, 0, 0, 0x80, 0x81, 97, 0x80];
+        bytes.extend(9u32.to_le_bytes());
+        bytes.push(0);
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend((packed.len() as u16).to_le_bytes());
+        bytes.extend(packed);
+        // The instrument, with one sample that every note plays
+        let mut instrument = vec![0; 263];
+        instrument[..4].copy_from_slice(&263u32.to_le_bytes());
+        instrument[27..29].copy_from_slice(&1u16.to_le_bytes());
+        instrument[29..33].copy_from_slice(&40u32.to_le_bytes());
+        bytes.extend(instrument);
+        let mut sample = vec![0; 40];
+        sample[..4].copy_from_slice(&4u32.to_le_bytes());
+        sample[12] = 64;
+        bytes.extend(sample);
+        // Samples are stored as the difference from the last value
+        bytes.extend([10, 10, -20i8 as u8, 5]);
+        bytes
+    }
+
+    #[test]
+    fn protracker_modules_convert() {
+        let it = from_protracker(&protracker()).unwrap();
+        let header = read_header(&it);
+        assert_eq!(header.orders, [0, ORDER_END]);
+        assert_eq!(header.flags & IT_INSTRUMENTS, 0);
+        assert!(header.instruments.is_empty());
+        assert_eq!(header.samples.len(), 31);
+        assert_eq!(&it[header.samples[0] + 0x14..][..4], b"lead");
+        assert_eq!(
+            sample_data(&it, header.samples[0]),
+            (0..32).map(|i| i * 4).collect::<Vec<u8>>()
+        );
+        assert!(sample_data(&it, header.samples[1]).is_empty());
+        let (rows, packed) = pattern(&it, header.patterns[0]);
+        assert_eq!(rows, 64);
+        // Channel 1 has a note, instrument, and volume, and then the row ends
+        assert_eq!(&packed[..6], [0x81, 0b0111, 60, 1, 32, 0]);
+        assert_eq!(packed.len(), 6 + 63);
+    }
+
+    #[test]
+    fn fasttracker_modules_convert() {
+        let it = from_fasttracker(&fasttracker()).unwrap();
+        let header = read_header(&it);
+        assert_eq!(header.orders, [0, ORDER_END]);
+        assert_ne!(header.flags & IT_INSTRUMENTS, 0);
+        assert_ne!(header.flags & IT_LINEAR, 0);
+        assert_eq!(header.instruments.len(), 1);
+        // Every note in the instrument plays sample 1
+        let keyboard = &it[header.instruments[0] + 0x40..][..240];
+        assert!(keyboard
+            .chunks(2)
+            .enumerate()
+            .all(|(note, key)| key == [note as u8, 1]));
+        assert_eq!(header.samples.len(), 1);
+        assert_eq!(sample_data(&it, header.samples[0]), [10, 20, 0, 5]);
+        let (rows, packed) = pattern(&it, header.patterns[0]);
+        assert_eq!(rows, 2);
+        assert_eq!(
+            packed,
+            [0x81, 0b0111, 60, 1, 64, 0, 0x81, 0b0001, NOTE_OFF, 0]
+        );
+    }
+
+    #[test]
+    fn cut_short_modules_dont_convert() {
+        let module = protracker();
+        assert!(from_protracker(&module[..1000]).is_err());
+        let module = fasttracker();
+        assert!(from_fasttracker(&module[..300]).is_err());
+    }
+
+    #[test]
+    fn patterns_too_big_for_it_dont_convert() {
+        // 64 channels and 200 rows, with every cell full, packs down to more than 64KiB in IT
+        let mut module = ItModule::new(b"big", MAX_CHANNELS);
+        let cell = Cell {
+            note: Some(60),
+            instrument: 1,
+            volume: Some(64),
+            command: CMD_VIBRATO,
+            param: 0x11,
+        };
+        module.orders = vec![0];
+        module.patterns = vec![vec![vec![cell; MAX_CHANNELS]; MAX_ROWS]];
+        assert_eq!(module.write(), Err(strings::pattern_too_big(0)));
+        module.patterns[0].truncate(64);
+        assert!(module.write().is_ok());
+    }
+}
@@ -0,0 +1,200 @@
+// Audio for cmdjewel.
+// Music is played with it2play, which opens its own output stream. If there's no output device (or cmdjewel was
+// built without the `audio` feature) everything in here quietly does nothing instead.
+
+use crate::config::data::Music;
+use crate::constants::strings;
+use module::ModuleFormat;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+mod convert;
+pub mod module;
+#[cfg(feature = "audio")]
+mod sfx;
+
+/// The soundtrack that comes with cmdjewel
+#[cfg(feature = "audio")]
+pub const BUNDLED_MODULE: &[u8] = include_bytes!("../../cmdjewel.it");
+
+/// Songs in the bundled module, and the orders they start at
+pub const BUNDLED_TRACKS: [(&str, u16); 6] = [
+    ("Intro", 0x00),
+    ("Return to the Network", 0x02),
+    ("Classic", 0x0d),
+    ("Zen", 0x4b),
+    ("Extra 1", 0x7f),
+    ("Extra 2", 0x81),
+];
+
+/// Whether an output stream was successfully opened.
+static AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the sound effect stream was successfully opened.
+static SFX_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// The custom module that's loaded (already converted to .it), kept so its songs can be listed without reading it
+/// again. None while the bundled soundtrack is loaded.
+static CUSTOM_MODULE: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+/// Keeps the audio stream alive. Music stops when this is dropped, so hold onto it until cmdjewel quits.
+pub struct AudioHandle {
+    #[cfg(feature = "audio")]
+    _stream: Option<cpal::Stream>,
+    #[cfg(feature = "audio")]
+    _sfx_stream: Option<cpal::Stream>,
+}
+
+/// Places in cmdjewel that can each have their own music
+#[derive(Copy, Clone)]
+pub enum Soundtrack {
+    Title,
+    Menu,
+    Classic,
+    Zen,
+    Poker,
+    IceStorm,
+}
+
+/// Sound effects that can be played
+pub enum Sfx {
+    Swap,
+    InvalidSwap,
+    /// A step in a cascade. Each step sounds higher than the last.
+    Cascade(#[cfg_attr(not(feature = "audio"), allow(dead_code))] u8),
+    Flame,
+    Star,
+    Hypercube,
+    LevelUp,
+    GameOver,
+    Shuffle,
+    /// A poker hand was scored
+    PokerHand,
+    /// Some ice was broken
+    IceBreak,
+}
+
+/// Loads a custom module (or the bundled soundtrack, if there isn't one) and gets ready to play it.
+/// If no audio device can be found, returns a handle to nothing and marks audio as unavailable.
+#[cfg(feature = "audio")]
+pub fn init(custom: Option<Vec<u8>>) -> AudioHandle {
+    use cpal::traits::{HostTrait, StreamTrait};
+    let module = custom.clone().unwrap_or_else(|| BUNDLED_MODULE.to_vec());
+    *CUSTOM_MODULE.lock().unwrap() = custom;
+    // Don't even try if there's no output device (servers, containers, SSH sessions...)
+    if cpal::default_host().default_output_device().is_none() {
+        return AudioHandle {
+            _stream: None,
+            _sfx_stream: None,
+        };
+    }
+    it2play_rs::load_bytes(module, it2play_rs::IT2Driver::HQ);
+    // it2play panics if it can't build a stream, so catch that and fall back to no audio.
+    let stream = std::panic::catch_unwind(it2play_rs::generate_stream)
+        .ok()
+        .filter(|stream| stream.play().is_ok());
+    AVAILABLE.store(stream.is_some(), Ordering::Relaxed);
+    let sfx_stream = sfx::init();
+    SFX_AVAILABLE.store(sfx_stream.is_some(), Ordering::Relaxed);
+    AudioHandle {
+        _stream: stream,
+        _sfx_stream: sfx_stream,
+    }
+}
+
+/// Without the `audio` feature there's nothing to play music with.
+#[cfg(not(feature = "audio"))]
+pub fn init(custom: Option<Vec<u8>>) -> AudioHandle {
+    *CUSTOM_MODULE.lock().unwrap() = custom;
+    AudioHandle {}
+}
+
+/// Returns true if music can be played.
+pub fn is_available() -> bool {
+    AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Returns true if sound effects can be played. The device might not take any sample format they can be mixed in.
+pub fn sfx_available() -> bool {
+    SFX_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Replaces the current module with a custom one, or the bundled soundtrack if there isn't one. Nothing plays until
+/// `play` is called.
+pub fn load(custom: Option<Vec<u8>>) {
+    #[cfg(feature = "audio")]
+    if is_available() {
+        let module = custom.clone().unwrap_or_else(|| BUNDLED_MODULE.to_vec());
+        it2play_rs::load_bytes(module, it2play_rs::IT2Driver::HQ);
+    }
+    *CUSTOM_MODULE.lock().unwrap() = custom;
+}
+
+/// Reads a module from disk, converting it to Impulse Tracker if it needs to be.
+pub fn read_module(path: &str) -> Result<Vec<u8>, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    match module::detect(&bytes) {
+        Some(ModuleFormat::It) => Ok(bytes),
+        Some(ModuleFormat::Xm) => convert::from_fasttracker(&bytes),
+        Some(ModuleFormat::Mod) => convert::from_protracker(&bytes),
+        None => Err(strings::MODULE_UNKNOWN.into()),
+    }
+}
+
+/// Lists the songs in the module that's loaded, as (name, order) pairs.
+pub fn tracks() -> Vec<(String, u16)> {
+    match CUSTOM_MODULE.lock().unwrap().as_deref() {
+        Some(module) => module::songs(module)
+            .iter()
+            .enumerate()
+            .map(|(i, &order)| (strings::track(i + 1, order), order))
+            .collect(),
+        None => BUNDLED_TRACKS
+            .iter()
+            .map(|&(name, order)| (name.to_string(), order))
+            .collect(),
+    }
+}
+
+/// Plays the music that's been picked for a soundtrack.
+pub fn play_soundtrack(music: &Music, soundtrack: Soundtrack) {
+    play(music.get(soundtrack));
+}
+
+/// Jumps to an order in the current module.
+pub fn play(order: u16) {
+    #[cfg(feature = "audio")]
+    if is_available() {
+        it2play_rs::play(order);
+    }
+    #[cfg(not(feature = "audio"))]
+    let _ = order;
+}
+
+/// Sets the music volume, between 0 and 1 inclusive.
+pub fn set_volume(volume: f32) {
+    #[cfg(feature = "audio")]
+    if is_available() {
+        it2play_rs::set_global_volume((volume * 128.) as u16);
+    }
+    #[cfg(not(feature = "audio"))]
+    let _ = volume;
+}
+
+/// Plays a sound effect.
+pub fn play_sfx(sfx: Sfx) {
+    #[cfg(feature = "audio")]
+    if sfx_available() {
+        sfx::play(sfx);
+    }
+    #[cfg(not(feature = "audio"))]
+    let _ = sfx;
+}
+
+/// Sets the sound effect volume, between 0 and 1 inclusive.
+pub fn set_sfx_volume(volume: f32) {
+    #[cfg(feature = "audio")]
+    sfx::set_volume(volume);
+    #[cfg(not(feature = "audio"))]
+    let _ = volume;
+}
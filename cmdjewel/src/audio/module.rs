@@ -0,0 +1,58 @@
+// Reading (just enough of) tracker modules to find out what's in them.
+
+/// Tracker module formats cmdjewel knows about
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum ModuleFormat {
+    /// Impulse Tracker (.it), which is what it2play plays
+    It,
+    /// FastTracker II (.xm), which gets converted to .it
+    Xm,
+    /// ProTracker and friends (.mod), which get converted to .it
+    Mod,
+}
+
+/// Figures out the format of a module from its header.
+pub fn detect(bytes: &[u8]) -> Option<ModuleFormat> {
+    if bytes.starts_with(b"IMPM") {
+        Some(ModuleFormat::It)
+    } else if bytes.starts_with(b"Extended Module: ") {
+        Some(ModuleFormat::Xm)
+    } else if protracker_channels(bytes).is_some() {
+        Some(ModuleFormat::Mod)
+    } else {
+        None
+    }
+}
+
+/// Gets the number of channels in a ProTracker module from the tag after its sample headers.
+/// Returns None if the tag isn't one we know.
+pub fn protracker_channels(bytes: &[u8]) -> Option<usize> {
+    match bytes.get(1080..1084)? {
+        b"M.K." | b"M!K!" | b"FLT4" => Some(4),
+        b"FLT8" => Some(8),
+        // xCHN and xxCH (FastTracker and TakeTracker)
+        [n, b'C', b'H', b'N'] if n.is_ascii_digit() => Some((n - b'0') as usize),
+        [a, b, b'C', b'H'] if a.is_ascii_digit() && b.is_ascii_digit() => {
+            Some(((a - b'0') * 10 + (b - b'0')) as usize)
+        }
+        _ => None,
+    }
+    .filter(|&channels| (1..=32).contains(&channels))
+}
+
+/// Finds every song in an Impulse Tracker module, returning the order each one starts at.
+/// Modules can hold more than one song by separating them with "end of song" (0xFF) markers in the order list.
+pub fn songs(bytes: &[u8]) -> Vec<u16> {
+    if detect(bytes) != Some(ModuleFormat::It) || bytes.len() < 0xC0 {
+        return vec![0];
+    }
+    let num_orders = u16::from_le_bytes([bytes[0x20], bytes[0x21]]) as usize;
+    let orders = &bytes[0xC0..(0xC0 + num_orders).min(bytes.len())];
+    let mut songs = vec![0];
+    orders.windows(2).enumerate().for_each(|(i, pair)| {
+        if pair[0] == 0xFF && pair[1] != 0xFF {
+            songs.push(i as u16 + 1);
+        }
+    });
+    songs
+}
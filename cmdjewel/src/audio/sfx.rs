@@ -0,0 +1,233 @@
+// Sound effects. These are synthesized on the fly (a few square/triangle/noise voices) and played on their own
+// output stream, so they don't have to fight it2play for the one it's using.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use super::Sfx;
+
+/// Voices that are currently playing (or waiting to play).
+static VOICES: Mutex<Vec<Voice>> = Mutex::new(Vec::new());
+
+/// Sound effect volume as the bits of an f32 between 0 and 1, so the audio thread can read it without locking.
+static VOLUME: AtomicU32 = AtomicU32::new(0x3f800000); // 1.0
+
+/// Waveforms voices can use
+#[derive(Copy, Clone)]
+enum Wave {
+    Square,
+    Triangle,
+    Noise,
+}
+
+/// A single note in a sound effect.
+#[derive(Copy, Clone)]
+struct Voice {
+    wave: Wave,
+    /// Starting frequency in Hz
+    freq: f32,
+    /// How much the frequency is multiplied by each second (e.g. 2.0 rises an octave per second)
+    sweep: f32,
+    /// Seconds to wait before playing
+    delay: f32,
+    /// Length in seconds
+    length: f32,
+    /// Seconds since the voice started (including the delay)
+    t: f32,
+    /// Where in the waveform we are, from 0 to 1
+    phase: f32,
+}
+
+impl Voice {
+    fn new(wave: Wave, freq: f32, length: f32) -> Self {
+        Voice {
+            wave,
+            freq,
+            sweep: 1.0,
+            delay: 0.0,
+            length,
+            t: 0.0,
+            phase: 0.0,
+        }
+    }
+
+    fn sweep(self, sweep: f32) -> Self {
+        Voice { sweep, ..self }
+    }
+
+    fn delay(self, delay: f32) -> Self {
+        Voice { delay, ..self }
+    }
+
+    /// Returns the next sample, advancing the voice by `dt` seconds. `noise` is a random number between -1 and 1.
+    fn sample(&mut self, dt: f32, noise: f32) -> f32 {
+        self.t += dt;
+        let t = self.t - self.delay;
+        if t < 0.0 {
+            return 0.0;
+        }
+        self.phase = (self.phase + self.freq * self.sweep.powf(t) * dt).fract();
+        let value = match self.wave {
+            Wave::Square => {
+                if self.phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Wave::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+            Wave::Noise => noise,
+        };
+        // Quick attack, linear decay
+        let envelope = (t / 0.005).min(1.0) * (1.0 - t / self.length).max(0.0);
+        value * envelope * 0.25
+    }
+
+    fn is_finished(&self) -> bool {
+        self.t >= self.delay + self.length
+    }
+}
+
+impl Sfx {
+    /// Gets the voices that make up a sound effect.
+    fn voices(&self) -> Vec<Voice> {
+        match self {
+            Sfx::Swap => vec![Voice::new(Wave::Triangle, 660.0, 0.06).sweep(1.5)],
+            Sfx::InvalidSwap => vec![
+                Voice::new(Wave::Square, 220.0, 0.08).sweep(0.5),
+                Voice::new(Wave::Square, 165.0, 0.1).sweep(0.5).delay(0.08),
+            ],
+            Sfx::Cascade(step) => {
+                // Up a whole tone each step, but don't go through the roof
+                let freq = 440.0 * 2f32.powf((*step).min(12) as f32 * 2.0 / 12.0);
+                vec![
+                    Voice::new(Wave::Triangle, freq, 0.12),
+                    Voice::new(Wave::Triangle, freq * 1.5, 0.1).delay(0.04),
+                ]
+            }
+            Sfx::Flame => vec![
+                Voice::new(Wave::Noise, 0.0, 0.35),
+                Voice::new(Wave::Square, 110.0, 0.25).sweep(0.3),
+            ],
+            Sfx::Star => vec![
+                Voice::new(Wave::Square, 440.0, 0.3).sweep(6.0),
+                Voice::new(Wave::Triangle, 880.0, 0.3)
+                    .sweep(6.0)
+                    .delay(0.05),
+                Voice::new(Wave::Noise, 0.0, 0.15),
+            ],
+            Sfx::Hypercube => (0..8)
+                .map(|i| {
+                    Voice::new(Wave::Triangle, 523.25 * 2f32.powf(i as f32 / 4.0), 0.1)
+                        .delay(i as f32 * 0.04)
+                })
+                .chain([Voice::new(Wave::Noise, 0.0, 0.5).delay(0.3)])
+                .collect(),
+            Sfx::LevelUp => [523.25, 659.25, 783.99, 1046.5]
+                .iter()
+                .enumerate()
+                .map(|(i, &freq)| Voice::new(Wave::Square, freq, 0.2).delay(i as f32 * 0.09))
+                .collect(),
+            Sfx::GameOver => [392.0, 329.63, 261.63, 196.0]
+                .iter()
+                .enumerate()
+                .map(|(i, &freq)| Voice::new(Wave::Square, freq, 0.3).delay(i as f32 * 0.2))
+                .collect(),
+            // A flurry of swaps
+            Sfx::Shuffle => (0..6)
+                .map(|i| {
+                    let freq = if i % 2 == 0 { 660.0 } else { 495.0 };
+                    Voice::new(Wave::Triangle, freq, 0.06)
+                        .sweep(1.5)
+                        .delay(i as f32 * 0.05)
+                })
+                .collect(),
+            // A chord, strummed
+            Sfx::PokerHand => [392.0, 493.88, 587.33, 783.99]
+                .iter()
+                .enumerate()
+                .map(|(i, &freq)| Voice::new(Wave::Triangle, freq, 0.4).delay(i as f32 * 0.03))
+                .collect(),
+            // Cracking, then a tinkle of falling shards
+            Sfx::IceBreak => vec![
+                Voice::new(Wave::Noise, 0.0, 0.15),
+                Voice::new(Wave::Triangle, 1567.98, 0.2)
+                    .sweep(0.5)
+                    .delay(0.05),
+                Voice::new(Wave::Triangle, 2093.0, 0.15)
+                    .sweep(0.5)
+                    .delay(0.1),
+            ],
+        }
+    }
+}
+
+/// Opens an output stream for sound effects. Returns None if it can't.
+pub fn init() -> Option<cpal::Stream> {
+    let device = cpal::default_host().default_output_device()?;
+    let config = device.default_output_config().ok()?;
+    // Mix in floating point, then convert to whatever the device wants
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.into()),
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.into()),
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.into()),
+        _ => None,
+    }?;
+    stream.play().ok()?;
+    Some(stream)
+}
+
+/// Builds a stream that mixes every playing voice into samples of type `T`.
+fn build_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig) -> Option<cpal::Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let dt = 1.0 / config.sample_rate.0 as f32;
+    let channels = config.channels as usize;
+    // xorshift, for noise
+    let mut seed: u32 = 0x9e3779b9;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                // Never wait on the game thread here. If it's adding voices right now, play silence and pick them
+                // up next time.
+                let Ok(mut voices) = VOICES.try_lock() else {
+                    data.fill(T::EQUILIBRIUM);
+                    return;
+                };
+                let volume = f32::from_bits(VOLUME.load(Ordering::Relaxed));
+                for frame in data.chunks_mut(channels) {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    let noise = seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                    let value = voices
+                        .iter_mut()
+                        .map(|voice| voice.sample(dt, noise))
+                        .sum::<f32>()
+                        * volume;
+                    let sample = T::from_sample(value.clamp(-1.0, 1.0));
+                    frame.iter_mut().for_each(|s| *s = sample);
+                }
+                voices.retain(|voice| !voice.is_finished());
+            },
+            |_| {},
+            None,
+        )
+        .ok()
+}
+
+/// Starts playing a sound effect.
+pub fn play(sfx: Sfx) {
+    // Make the voices before locking, so the audio thread is kept waiting as little as possible
+    let mut voices = sfx.voices();
+    VOICES.lock().unwrap().append(&mut voices);
+}
+
+/// Sets the sound effect volume, between 0 and 1 inclusive.
+pub fn set_volume(volume: f32) {
+    VOLUME.store(volume.to_bits(), Ordering::Relaxed);
+}
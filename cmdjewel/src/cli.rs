@@ -0,0 +1,52 @@
+// Command line arguments. These take precedence over settings, but only for as long as cmdjewel is running.
+
+use crate::theme::{depth::ColorDepth, glyphs::GlyphSet};
+
+const USAGE: &str = "Usage: cmdjewel [OPTIONS]
+
+Options:
+  --glyphs=<SET>    Draw gems with unicode, ascii, or letters
+  --ascii           Same as --glyphs=ascii
+  --colors=<DEPTH>  Draw with auto, truecolor, 256, or 16 colors
+  --debug           Allow debug commands in the console
+  -h, --help        Print this message";
+
+/// Options given on the command line
+#[derive(Default)]
+pub struct Args {
+    pub glyphs: Option<GlyphSet>,
+    pub colors: Option<ColorDepth>,
+    pub debug: bool,
+}
+
+impl Args {
+    /// Reads arguments given to cmdjewel. If there's anything it doesn't understand, it prints usage and exits.
+    pub fn parse() -> Args {
+        match Args::try_parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(e) => {
+                if !e.is_empty() {
+                    eprintln!("cmdjewel: {}\n", e);
+                }
+                eprintln!("{}", USAGE);
+                std::process::exit(if e.is_empty() { 0 } else { 2 });
+            }
+        }
+    }
+
+    /// Parses arguments. An empty error means help was asked for.
+    fn try_parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        for arg in args {
+            match arg.split_once('=') {
+                Some(("--glyphs", value)) => parsed.glyphs = Some(value.parse()?),
+                Some(("--colors", value)) => parsed.colors = Some(value.parse()?),
+                _ if arg == "--ascii" => parsed.glyphs = Some(GlyphSet::Ascii),
+                _ if arg == "--debug" => parsed.debug = true,
+                _ if arg == "-h" || arg == "--help" => return Err(String::new()),
+                _ => return Err(format!("unknown argument \"{}\"", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -1,8 +1,18 @@
 use cmdjewel_core::{
     board::Board,
     gems::{Gem, GemColor, GemSelector},
+    ice,
+    levels::LevelStats,
 };
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::animations::AnimationSpeed;
+use crate::audio::Soundtrack;
+use crate::config::keys::Keybindings;
+use crate::theme::{
+    cells::CellSize, colorblind::ColorblindMode, depth::ColorDepth, glyphs::GlyphSet,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
@@ -13,15 +23,59 @@ pub struct Config {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     /// Music volume between 0 and 1, inclusive
     pub music_vol: f32,
+    /// Sound effect volume between 0 and 1, inclusive
+    pub sfx_vol: f32,
+    /// Which music plays where
+    pub music: Music,
+    /// Name of the color theme
+    pub theme: String,
+    /// Gem colors and markers for colorblind players
+    pub colorblind: ColorblindMode,
+    /// Characters gems are drawn with
+    pub glyphs: GlyphSet,
+    /// How many colors the terminal can show
+    pub color_depth: ColorDepth,
+    /// Keys for each in-game action
+    pub keys: Keybindings,
+    /// If the cursor follows gems after they're swapped
+    pub sticky_swap: bool,
+    /// How fast gems swap and fall
+    pub animation_speed: AnimationSpeed,
+    /// How big gems are drawn
+    pub cell_size: CellSize,
+    /// If new boards and refills are made so nothing matches (and scores) before the player's made a move. Only
+    /// applies to games started after it's changed.
+    pub no_free_matches: bool,
+    /// If Zen levels have goals to meet instead of a progress bar. Only applies to games started after it's changed.
+    pub zen_goals: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Music {
+    /// Orders (in the module) to start playing from for each soundtrack
+    pub title: u16,
+    pub menu: u16,
+    pub classic: u16,
+    pub zen: u16,
+    pub poker: u16,
+    pub ice_storm: u16,
+    /// Path to a module to play instead of the bundled soundtrack
+    pub custom_module: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Save {
     pub classic: Option<GameSave>,
     pub zen: Option<GameSave>,
+    #[serde(default)]
+    pub poker: Option<GameSave>,
+    #[serde(default)]
+    pub ice_storm: Option<GameSave>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -30,20 +84,95 @@ pub struct GameSave {
     pub score: u32,
     pub level: u8,
     pub level_progress: f32,
+    /// Shuffles left. Saves from before shuffles existed don't have this, so they get however many the gamemode starts
+    /// with.
+    #[serde(default)]
+    pub shuffles_left: Option<u8>,
+    /// What's happened so far this level. Saves from before this was kept start the level's stats over.
+    #[serde(default)]
+    pub level_stats: Option<LevelStatsWrapper>,
+    /// Colors dealt into the poker hand so far (in Poker)
+    #[serde(default)]
+    pub hand: Vec<GemColorWrapper>,
+    /// How far up each column ice has got (in Ice Storm)
+    #[serde(default)]
+    pub ice: Option<[u8; ice::COLUMNS]>,
 }
 
 impl std::default::Default for Config {
     fn default() -> Self {
         Config {
-            settings: Settings { music_vol: 1.0 },
+            settings: Settings::default(),
             save: Save {
                 classic: None,
                 zen: None,
+                poker: None,
+                ice_storm: None,
             },
         }
     }
 }
 
+impl std::default::Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_vol: 1.0,
+            sfx_vol: 1.0,
+            music: Music::default(),
+            theme: "Nord".into(),
+            colorblind: ColorblindMode::Off,
+            glyphs: GlyphSet::Unicode,
+            color_depth: ColorDepth::Auto,
+            keys: Keybindings::default(),
+            sticky_swap: false,
+            animation_speed: AnimationSpeed::Normal,
+            cell_size: CellSize::Auto,
+            no_free_matches: true,
+            zen_goals: false,
+        }
+    }
+}
+
+impl std::default::Default for Music {
+    fn default() -> Self {
+        Music {
+            title: 0x00,
+            menu: 0x02,
+            classic: 0x0d,
+            zen: 0x4b,
+            poker: 0x0d,
+            ice_storm: 0x0d,
+            custom_module: None,
+        }
+    }
+}
+
+impl Music {
+    /// Gets the order to play for a soundtrack
+    pub fn get(&self, soundtrack: Soundtrack) -> u16 {
+        match soundtrack {
+            Soundtrack::Title => self.title,
+            Soundtrack::Menu => self.menu,
+            Soundtrack::Classic => self.classic,
+            Soundtrack::Zen => self.zen,
+            Soundtrack::Poker => self.poker,
+            Soundtrack::IceStorm => self.ice_storm,
+        }
+    }
+
+    /// Sets the order to play for a soundtrack
+    pub fn set(&mut self, soundtrack: Soundtrack, order: u16) {
+        match soundtrack {
+            Soundtrack::Title => self.title = order,
+            Soundtrack::Menu => self.menu = order,
+            Soundtrack::Classic => self.classic = order,
+            Soundtrack::Zen => self.zen = order,
+            Soundtrack::Poker => self.poker = order,
+            Soundtrack::IceStorm => self.ice_storm = order,
+        }
+    }
+}
+
 impl GameSave {
     pub fn new(board: &Board) -> Self {
         GameSave {
@@ -55,10 +184,37 @@ impl GameSave {
             score: board.get_score(),
             level: board.get_level(),
             level_progress: board.get_level_progress(),
+            shuffles_left: Some(board.get_shuffles_left()),
+            level_stats: Some(LevelStatsWrapper(*board.get_level_stats())),
+            hand: board
+                .get_hand()
+                .cards()
+                .iter()
+                .map(|&c| GemColorWrapper(c))
+                .collect(),
+            ice: Some(board.get_ice().heights()),
         }
     }
 }
 
+/*
+   Redefine level stats
+*/
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelStatsWrapper(#[serde(with = "LevelStatsDef")] pub LevelStats);
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "LevelStats")]
+pub struct LevelStatsDef {
+    pub points: u32,
+    pub best_move: u32,
+    pub moves: u32,
+    pub cleared: [u32; 7],
+    pub made: [u32; 4],
+    pub time: Duration,
+}
+
 /*
    Redefine gems
 */
@@ -86,6 +242,9 @@ pub enum GemDef {
     Hypercube(GemSelector),
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GemColorWrapper(#[serde(with = "GemColorDef")] pub GemColor);
+
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "GemColor")]
 pub enum GemColorDef {
@@ -0,0 +1,336 @@
+// Keybindings. Keys are stored in the config as strings like "Left", "h", "Shift+Up", or "Ctrl+z" so that they're
+// easy to edit by hand, and get turned into cursive events when they're used.
+
+use cursive::event::{Event, Key};
+use serde::{Deserialize, Serialize};
+
+/// Things a key can do in-game
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    ToggleSwap,
+    SwapLeft,
+    SwapRight,
+    SwapUp,
+    SwapDown,
+    Hint,
+    Undo,
+    Shuffle,
+    Pause,
+    Command,
+}
+
+impl Action {
+    /// Every action, in the order they're shown in settings
+    pub const ALL: [Action; 14] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::ToggleSwap,
+        Action::SwapLeft,
+        Action::SwapRight,
+        Action::SwapUp,
+        Action::SwapDown,
+        Action::Hint,
+        Action::Undo,
+        Action::Shuffle,
+        Action::Pause,
+        Action::Command,
+    ];
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::MoveLeft => write!(f, "Move left"),
+            Action::MoveRight => write!(f, "Move right"),
+            Action::MoveUp => write!(f, "Move up"),
+            Action::MoveDown => write!(f, "Move down"),
+            Action::ToggleSwap => write!(f, "Swap mode"),
+            Action::SwapLeft => write!(f, "Swap left"),
+            Action::SwapRight => write!(f, "Swap right"),
+            Action::SwapUp => write!(f, "Swap up"),
+            Action::SwapDown => write!(f, "Swap down"),
+            Action::Hint => write!(f, "Hint"),
+            Action::Undo => write!(f, "Undo"),
+            Action::Shuffle => write!(f, "Shuffle"),
+            Action::Pause => write!(f, "Pause"),
+            Action::Command => write!(f, "Command"),
+        }
+    }
+}
+
+/// Sets of keybindings to start from
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Preset {
+    /// Arrow keys and Vim keys
+    Default,
+    /// Only Vim keys
+    Vim,
+    /// WASD, like in most PC games
+    Wasd,
+    /// The number pad (with Num Lock on)
+    Numpad,
+}
+
+impl Preset {
+    /// Every preset, in the order they're shown in settings
+    pub const ALL: [Preset; 4] = [Preset::Default, Preset::Vim, Preset::Wasd, Preset::Numpad];
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Preset::Default => write!(f, "Default"),
+            Preset::Vim => write!(f, "Vim"),
+            Preset::Wasd => write!(f, "WASD"),
+            Preset::Numpad => write!(f, "Numpad"),
+        }
+    }
+}
+
+/// Keys for each action
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Keybindings {
+    pub move_left: Vec<String>,
+    pub move_right: Vec<String>,
+    pub move_up: Vec<String>,
+    pub move_down: Vec<String>,
+    pub toggle_swap: Vec<String>,
+    pub swap_left: Vec<String>,
+    pub swap_right: Vec<String>,
+    pub swap_up: Vec<String>,
+    pub swap_down: Vec<String>,
+    pub hint: Vec<String>,
+    pub undo: Vec<String>,
+    pub shuffle: Vec<String>,
+    pub pause: Vec<String>,
+    pub command: Vec<String>,
+}
+
+impl Keybindings {
+    /// Creates keybindings from a preset.
+    pub fn preset(preset: Preset) -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        match preset {
+            // Capital Vim keys move too, like they always have
+            Preset::Default => Keybindings {
+                move_left: keys(&["Left", "h", "H"]),
+                move_right: keys(&["Right", "l", "L"]),
+                move_up: keys(&["Up", "k", "K"]),
+                move_down: keys(&["Down", "j", "J"]),
+                toggle_swap: keys(&["Space", "Enter"]),
+                swap_left: keys(&["Shift+Left"]),
+                swap_right: keys(&["Shift+Right"]),
+                swap_up: keys(&["Shift+Up"]),
+                swap_down: keys(&["Shift+Down"]),
+                hint: keys(&["?"]),
+                undo: keys(&["u"]),
+                shuffle: keys(&["s"]),
+                pause: keys(&["Esc", "p"]),
+                command: keys(&[":"]),
+            },
+            Preset::Vim => Keybindings {
+                move_left: keys(&["h"]),
+                move_right: keys(&["l"]),
+                move_up: keys(&["k"]),
+                move_down: keys(&["j"]),
+                toggle_swap: keys(&["Space", "Enter"]),
+                swap_left: keys(&["H"]),
+                swap_right: keys(&["L"]),
+                swap_up: keys(&["K"]),
+                swap_down: keys(&["J"]),
+                hint: keys(&["?"]),
+                undo: keys(&["u"]),
+                shuffle: keys(&["s"]),
+                pause: keys(&["Esc", "p"]),
+                command: keys(&[":"]),
+            },
+            Preset::Wasd => Keybindings {
+                move_left: keys(&["a"]),
+                move_right: keys(&["d"]),
+                move_up: keys(&["w"]),
+                move_down: keys(&["s"]),
+                toggle_swap: keys(&["Space", "Enter"]),
+                swap_left: keys(&["A"]),
+                swap_right: keys(&["D"]),
+                swap_up: keys(&["W"]),
+                swap_down: keys(&["S"]),
+                hint: keys(&["h"]),
+                undo: keys(&["z"]),
+                shuffle: keys(&["x"]),
+                pause: keys(&["Esc", "p"]),
+                command: keys(&[":"]),
+            },
+            Preset::Numpad => Keybindings {
+                move_left: keys(&["4"]),
+                move_right: keys(&["6"]),
+                move_up: keys(&["8"]),
+                move_down: keys(&["2"]),
+                toggle_swap: keys(&["5", "0", "Enter"]),
+                swap_left: vec![],
+                swap_right: vec![],
+                swap_up: vec![],
+                swap_down: vec![],
+                hint: keys(&["+"]),
+                undo: keys(&["-"]),
+                shuffle: keys(&["."]),
+                pause: keys(&["*", "Esc"]),
+                command: keys(&["/"]),
+            },
+        }
+    }
+
+    /// Gets the keys for an action.
+    pub fn get(&self, action: Action) -> &Vec<String> {
+        match action {
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::ToggleSwap => &self.toggle_swap,
+            Action::SwapLeft => &self.swap_left,
+            Action::SwapRight => &self.swap_right,
+            Action::SwapUp => &self.swap_up,
+            Action::SwapDown => &self.swap_down,
+            Action::Hint => &self.hint,
+            Action::Undo => &self.undo,
+            Action::Shuffle => &self.shuffle,
+            Action::Pause => &self.pause,
+            Action::Command => &self.command,
+        }
+    }
+
+    /// Gets the keys for an action, mutably.
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<String> {
+        match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::ToggleSwap => &mut self.toggle_swap,
+            Action::SwapLeft => &mut self.swap_left,
+            Action::SwapRight => &mut self.swap_right,
+            Action::SwapUp => &mut self.swap_up,
+            Action::SwapDown => &mut self.swap_down,
+            Action::Hint => &mut self.hint,
+            Action::Undo => &mut self.undo,
+            Action::Shuffle => &mut self.shuffle,
+            Action::Pause => &mut self.pause,
+            Action::Command => &mut self.command,
+        }
+    }
+
+    /// Binds a key to an action. A key can only do one thing, so it's unbound from everything else first.
+    pub fn bind(&mut self, action: Action, key: String) {
+        Action::ALL
+            .iter()
+            .for_each(|&a| self.get_mut(a).retain(|k| *k != key));
+        self.get_mut(action).push(key);
+    }
+
+    /// Gets the event for every key that's bound, along with the action it triggers. Keys that can't be parsed are
+    /// skipped.
+    pub fn events(&self) -> Vec<(Event, Action)> {
+        Action::ALL
+            .into_iter()
+            .flat_map(|action| {
+                self.get(action)
+                    .iter()
+                    .filter_map(move |key| parse_key(key).map(|event| (event, action)))
+            })
+            .collect()
+    }
+}
+
+impl std::default::Default for Keybindings {
+    fn default() -> Self {
+        Keybindings::preset(Preset::Default)
+    }
+}
+
+/// Names of keys that aren't characters
+const NAMED_KEYS: [(&str, Key); 15] = [
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Enter", Key::Enter),
+    ("Tab", Key::Tab),
+    ("Backspace", Key::Backspace),
+    ("Esc", Key::Esc),
+    ("Insert", Key::Ins),
+    ("Delete", Key::Del),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("NumpadCenter", Key::NumpadCenter),
+];
+
+/// Turns a key name into a cursive key.
+fn parse_named_key(name: &str) -> Option<Key> {
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=12).contains(&n).then(|| Key::from_f(n));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+/// Gets the name of a cursive key.
+fn describe_named_key(key: Key) -> Option<String> {
+    if let Some(n) = (1..=12).find(|&n| Key::from_f(n) == key) {
+        return Some(format!("F{}", n));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|&&(_, k)| k == key)
+        .map(|(name, _)| name.to_string())
+}
+
+/// Turns a key string (like "Shift+Left" or "h") into a cursive event.
+pub fn parse_key(key: &str) -> Option<Event> {
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    if key == "Space" {
+        return Some(Event::Char(' '));
+    }
+    if let Some(c) = single(key) {
+        return Some(Event::Char(c));
+    }
+    if let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+        return match (modifier, single(rest), parse_named_key(rest)) {
+            ("Ctrl", Some(c), _) => Some(Event::CtrlChar(c.to_ascii_lowercase())),
+            ("Alt", Some(c), _) => Some(Event::AltChar(c)),
+            ("Shift", _, Some(k)) => Some(Event::Shift(k)),
+            ("Ctrl", _, Some(k)) => Some(Event::Ctrl(k)),
+            ("Alt", _, Some(k)) => Some(Event::Alt(k)),
+            _ => None,
+        };
+    }
+    parse_named_key(key).map(Event::Key)
+}
+
+/// Turns a cursive event into a key string, if it's a key press that can be bound.
+pub fn describe_key(event: &Event) -> Option<String> {
+    match event {
+        Event::Char(' ') => Some("Space".into()),
+        Event::Char(c) => Some(c.to_string()),
+        Event::CtrlChar(c) => Some(format!("Ctrl+{}", c)),
+        Event::AltChar(c) => Some(format!("Alt+{}", c)),
+        Event::Key(k) => describe_named_key(*k),
+        Event::Shift(k) => describe_named_key(*k).map(|k| format!("Shift+{}", k)),
+        Event::Ctrl(k) => describe_named_key(*k).map(|k| format!("Ctrl+{}", k)),
+        Event::Alt(k) => describe_named_key(*k).map(|k| format!("Alt+{}", k)),
+        _ => None,
+    }
+}
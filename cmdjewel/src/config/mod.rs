@@ -1,6 +1,8 @@
 use crate::constants;
 use cmdjewel_core::board::{Board, BoardConfig, Gamemode};
-use cmdjewel_core::gems::Gem;
+use cmdjewel_core::gems::{Gem, GemColor};
+use cmdjewel_core::ice::Ice;
+use cmdjewel_core::poker::Hand;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
@@ -8,6 +10,7 @@ use toml_edit::visit_mut::VisitMut;
 
 pub mod data;
 mod hacks;
+pub mod keys;
 
 /// Loads a config file and returns a corresponding Config struct.
 /// If:
@@ -58,6 +61,8 @@ pub fn save_board(board: &Board, is_game_over: bool) {
     match board.config_ref().gamemode {
         Gamemode::ZEN => cfg.save.zen = gs,
         Gamemode::CLASSIC => cfg.save.classic = gs,
+        Gamemode::POKER => cfg.save.poker = gs,
+        Gamemode::ICESTORM => cfg.save.ice_storm = gs,
     };
     // Write to config file
     save_config(&cfg);
@@ -81,6 +86,26 @@ pub fn save_config(cfg: &data::Config) {
     }
 }
 
+/// Loads commands typed into the console, oldest first. Empty if there aren't any yet.
+pub fn load_history() -> Vec<String> {
+    config_path()
+        .map(|p| p.with_file_name(constants::HISTORY_FILE))
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .map(|data| data.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Writes commands typed into the console, keeping only the most recent ones.
+pub fn save_history(history: &[String]) {
+    if let Some(path) = config_path().map(|p| p.with_file_name(constants::HISTORY_FILE)) {
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder).unwrap_or_default();
+        }
+        let start = history.len().saturating_sub(constants::HISTORY_LENGTH);
+        std::fs::write(path, history[start..].join("\n")).unwrap_or_default();
+    }
+}
+
 /// Deletes the config file, if it exists.
 pub fn reset_config() {
     if let Some(dir) = dirs::config_local_dir() {
@@ -92,13 +117,16 @@ pub fn reset_config() {
 }
 
 /// Creates a new Board. If a save exists for its gamemode, loads the save. Otherwise, creates a new Board.
-pub fn new_board(config: BoardConfig) -> Board {
+pub fn new_board(mut config: BoardConfig) -> Board {
     // Load the config (or the default config)
     let cfg = load_config();
+    config.no_free_matches = cfg.settings.no_free_matches;
     // Get game save
     let gs = match config.gamemode {
         Gamemode::ZEN => cfg.save.zen,
         Gamemode::CLASSIC => cfg.save.classic,
+        Gamemode::POKER => cfg.save.poker,
+        Gamemode::ICESTORM => cfg.save.ice_storm,
     };
     // Create Board
     if let Some(save) = gs {
@@ -106,7 +134,20 @@ pub fn new_board(config: BoardConfig) -> Board {
         let mut data = [Gem::Empty; 64];
         let save_data = save.data.iter().map(|g| g.0).collect::<Vec<Gem>>();
         data.copy_from_slice(&save_data.as_slice());
-        Board::new_controlled(config, data, save.score, save.level, save.level_progress)
+        let mut board =
+            Board::new_controlled(config, data, save.score, save.level, save.level_progress);
+        if let Some(shuffles) = save.shuffles_left {
+            board.set_shuffles_left(shuffles);
+        }
+        if let Some(stats) = save.level_stats {
+            board.set_level_stats(stats.0);
+        }
+        let hand: Vec<GemColor> = save.hand.iter().map(|c| c.0).collect();
+        board.set_hand(Hand::new(&hand));
+        if let Some(ice) = save.ice {
+            board.set_ice(Ice::new(ice));
+        }
+        board
     } else {
         Board::new(config)
     }
@@ -1,42 +1,89 @@
 pub const CONFIG_PATH: &str = "cmdjewel/config.toml";
+/// Folder (next to the config file) that custom themes are loaded from
+pub const THEMES_DIR: &str = "themes";
+/// File (next to the config file) that console commands are remembered in
+pub const HISTORY_FILE: &str = "history";
+/// How many console commands are remembered
+pub const HISTORY_LENGTH: usize = 100;
 
 pub mod strings {
+    use cmdjewel_core::gems::GemColor;
+    use cmdjewel_core::levels::{Goal, LevelStats, Special};
+    use cmdjewel_core::poker::PokerHand;
+    use std::time::Duration;
+
     pub const CMDJEWEL_LOGO: &str = "
                ,   .                _.
   __  ,   ,  _.| __.  __  ,   ,  __  |
  /  ' |\\ /| /  |   | /__' | , | /__' |
  \\__, | ' | \\_,|   , \\__, \\/ \\/ \\__, ',_
                  -'
-";
-    pub const LOGO_GEMS: &str = "
-       ●   ◆   ⬟   ▼   ■   ⬢   ▲
 ";
     pub const CLASSIC: &str = "Classic";
     pub const CLASSIC_DESC: &str =
         "A classic game of cmdjewel. Match 3 (or more) gems in a row until you run out of moves.";
     pub const ZEN: &str = "Zen";
     pub const ZEN_DESC: &str = "Like Classic, but you can't run out of moves.";
+    pub const POKER: &str = "Poker";
+    pub const POKER_DESC: &str =
+        "Every match deals its color into a hand of five. Full hands score like poker: pairs, flushes, full houses...";
+    pub const ICE_STORM: &str = "Ice Storm";
+    pub const ICE_STORM_DESC: &str =
+        "Ice creeps up the board as you play. Break it with vertical matches before any column freezes solid.";
     pub const MSG_WELCOME: &str =
         "Welcome to cmdjewel!\nUse the arrow keys and enter to move around.";
     pub const MAIN_MENU: &str = "Main Menu";
     pub const QUIT: &str = "Quit";
+    pub const PAUSE: &str = "Pause";
+    pub const PAUSED: &str = "Paused";
+    pub const RESUME: &str = "Resume";
+    pub const RESTART: &str = "Restart";
+    pub const SAVE_QUIT: &str = "Save & quit to menu";
     pub const OK: &str = "Ok";
     pub const PLAY: &str = "Play Game";
     pub const HELP: &str = "Help";
     pub const LEVEL: &str = "Level";
     pub const SCORE: &str = "Score";
     pub const HINT: &str = "Hint";
+    pub const SHUFFLE: &str = "Shuffle";
+    pub const SHUFFLED: &str = "Shuffle!";
+    pub const END_GAME: &str = "End game";
+    pub const CONTINUE: &str = "Continue";
     pub const COMMAND: &str = "Command";
-    pub const CMD_NOT_FOUND: &str =
-        "Command not found. Available commands are main/m, play/p [classic/zen], q[a/!], hint/h";
-    pub const KEY_NOT_FOUND: &str =
-        "Key not recognized. Use the arrow keys to move and the enter key to enter SWAP mode.";
+    pub const COMMANDS: &str = "Commands";
+    pub const NO_GAME: &str = "There isn't a game going.";
+    pub const TOO_MANY_ARGS: &str = "That's too many arguments.";
     pub const SETTINGS: &str = "Settings";
     pub const BACK: &str = "Back";
     pub const RESET: &str = "Reset";
     pub const YES: &str = "Yes";
     pub const NO: &str = "No";
     pub const MUSIC_VOL: &str = "Music volume";
+    pub const SFX_VOL: &str = "Sound volume";
+    pub const MUSIC: &str = "Music";
+    pub const THEME: &str = "Theme";
+    pub const COLORBLIND: &str = "Colorblind";
+    pub const GLYPHS: &str = "Gem glyphs";
+    pub const COLORS: &str = "Colors";
+    pub const KEYS: &str = "Keys";
+    pub const STICKY_SWAP: &str = "Sticky swap";
+    pub const FAIR_BOARDS: &str = "Fair boards";
+    pub const ZEN_GOALS: &str = "Zen goals";
+    pub const ANIMATION_SPEED: &str = "Speed";
+    pub const CELL_SIZE: &str = "Gem size";
+    pub const PRESET: &str = "Preset";
+    pub const NO_KEYS: &str = "(none)";
+    pub const TITLE_SCREEN: &str = "Title screen";
+    pub const CUSTOM_MODULE: &str = "Play a module from a file instead (path):";
+    pub const LOAD: &str = "Load";
+    pub const USE_BUNDLED: &str = "Use default";
+    pub const MODULE_BROKEN: &str =
+        "That module seems to be cut short or damaged, so it can't be played.";
+    pub const MODULE_UNKNOWN: &str = "That doesn't look like a .it, .xm, or .mod file.";
+    pub const AUDIO_UNAVAILABLE: &str =
+        "No audio device was found, so music and sounds are turned off. See Troubleshooting for help.";
+    pub const SFX_UNAVAILABLE: &str =
+        "Your audio device doesn't take a sample format sound effects can be played in, so they're turned off.";
     pub const TROUBLESHOOTING: &str = "Troubleshooting";
     pub const TUTORIAL: &str = "How to play";
     pub const TUTORIAL_1: &str = "Score as many points as possible until there are no more moves.";
@@ -46,15 +93,177 @@ pub mod strings {
     pub const HELP_TUTORIAL: &str = "Press the tutorial button to learn how to play cmdjewel.";
     pub const HELP_CMDJEWEL: &str =
         "cmdjewel is a match-3 game for terminal emulators, inspired by Bejeweled.";
-    pub const HELP_CONTROLS: &str = "You can use your mouse with cmdjewel. Try clicking on buttons! Click and swipe to match gems. If you're using the keyboard, press SPACE or ENTER in-game to enter swap mode - where directional keys will swap gems. You can even use Vim keys (h, j, k, and l) as directional keys.";
+    pub const HELP_CONTROLS: &str = "You can use your mouse with cmdjewel. Try clicking on buttons! Click and swipe to match gems. If you're using the keyboard, press SPACE or ENTER in-game to enter swap mode - where directional keys will swap gems. You can even use Vim keys (h, j, k, and l) as directional keys. To swap in one go, hold Shift with an arrow key (or pick the Vim preset, which swaps with H, J, K, and L). Press ? for a hint (and again to see exactly which gem to move), u to undo your last move, s to shuffle the board, or Esc or p to pause. All of these keys can be changed in the settings.";
     pub const WARN_RESET: &str =
         "This will delete your config file, including all your saved games.";
     pub const ARE_SURE: &str = "Are you sure?";
+    pub const WARN_RESTART: &str = "This will throw away your current game.";
+    /// Shown for long cascades, starting with the third clear in a row
+    pub const CALLOUTS: [&str; 5] = [
+        "Good!",
+        "Excellent!",
+        "Awesome!",
+        "Spectacular!",
+        "Unbelievable!",
+    ];
 
     pub fn first_save(path: &str) -> String {
         format!("cmdjewel just created a save file at {}. When you return, it'll load your game from that path.", path)
     }
 
+    pub fn key_prompt(action: &str) -> String {
+        format!(
+            "Press a key for \"{}\".\nEsc cancels, and Backspace clears every key for it, so neither can be bound here (every preset pauses with Esc).",
+            action
+        )
+    }
+
+    pub fn module_error(error: &str) -> String {
+        format!("Couldn't load that module: {}", error)
+    }
+
+    pub fn custom_module_error(error: &str) -> String {
+        format!(
+            "Couldn't load your custom module, so the default soundtrack is playing instead: {}",
+            error
+        )
+    }
+
+    pub fn pattern_too_big(pattern: usize) -> String {
+        format!(
+            "Pattern {} has too many notes and effects in it to be converted to .it.",
+            pattern
+        )
+    }
+
+    pub fn track(number: usize, order: u16) -> String {
+        format!("Song {} (order {})", number, order)
+    }
+
+    pub fn unknown_command(name: &str) -> String {
+        format!(
+            "There's no command called \"{}\". Type :help to see every command.",
+            name
+        )
+    }
+
+    pub fn missing_arg(name: &str) -> String {
+        format!("This needs a {}.", name)
+    }
+
+    pub fn not_a_number(word: &str) -> String {
+        format!("\"{}\" isn't a number.", word)
+    }
+
+    pub fn out_of_range(name: &str, min: i64, max: i64) -> String {
+        format!("The {} has to be from {} to {}.", name, min, max)
+    }
+
+    pub fn not_a_choice(word: &str, choices: &[&str]) -> String {
+        format!("\"{}\" isn't one of {}.", word, choices.join(", "))
+    }
+
+    pub fn bad_usage(problem: &str, usage: &str) -> String {
+        format!("{}\nUsage: {}", problem, usage)
+    }
+
+    pub fn aliases(aliases: &[&str]) -> String {
+        format!("\nAlso :{}", aliases.join(", :"))
+    }
+
+    pub fn popup_points(points: u32, cascade: u8) -> String {
+        if cascade == 0 {
+            format!("+{}", points)
+        } else {
+            format!("+{} x{}", points, cascade as u32 + 1)
+        }
+    }
+
+    pub fn shuffle_button(shuffles: u8) -> String {
+        format!("{} ({})", SHUFFLE, shuffles)
+    }
+
+    pub fn no_moves(shuffles: u8, penalty: u32) -> String {
+        let cost = if penalty > 0 {
+            format!(" for {} points", penalty)
+        } else {
+            String::new()
+        };
+        let left = if shuffles == 1 {
+            "1 shuffle".to_string()
+        } else {
+            format!("{} shuffles", shuffles)
+        };
+        format!(
+            "No more moves! You can shuffle the board{} ({} left).",
+            cost, left
+        )
+    }
+
+    pub fn color_name(color: GemColor) -> &'static str {
+        match color {
+            GemColor::Blue => "blue",
+            GemColor::White => "white",
+            GemColor::Red => "red",
+            GemColor::Yellow => "yellow",
+            GemColor::Green => "green",
+            GemColor::Orange => "orange",
+            GemColor::Purple => "purple",
+        }
+    }
+
+    pub fn special_name(special: Special) -> &'static str {
+        match special {
+            Special::Flame => "flame",
+            Special::Star => "star",
+            Special::Hypercube => "hypercube",
+            Special::Supernova => "supernova",
+        }
+    }
+
+    /// Gets a line for a goal and how much of it is done, like "Clear red gems: 12/20".
+    pub fn goal(goal: Goal, stats: &LevelStats) -> String {
+        let what = match goal {
+            Goal::Clear(color, _) => format!("Clear {} gems", color_name(color)),
+            Goal::Make(special, _) => format!("Make {} gems", special_name(special)),
+            Goal::Score(_) => "Score points".to_string(),
+        };
+        let check = if goal.is_met(stats) { " ✓" } else { "" };
+        format!("{}: {}/{}{}", what, goal.done(stats), goal.target(), check)
+    }
+
+    /// Gets a time as minutes and seconds, like "3:07".
+    pub fn duration(time: Duration) -> String {
+        let seconds = time.as_secs();
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    pub fn level_complete(level: u8) -> String {
+        format!("{} {} complete!", LEVEL, level)
+    }
+
+    pub fn level_summary(stats: &LevelStats) -> String {
+        format!(
+            "Points: {}\nBest move: {}\nSpecial gems made: {}\nTime: {}",
+            stats.points,
+            stats.best_move,
+            stats.made_total(),
+            duration(stats.time)
+        )
+    }
+
+    pub fn poker_hand(hand: PokerHand) -> &'static str {
+        match hand {
+            PokerHand::Pair => "Pair",
+            PokerHand::TwoPair => "Two pair",
+            PokerHand::ThreeOfAKind => "Three of a kind",
+            PokerHand::Spectrum => "Spectrum",
+            PokerHand::FullHouse => "Full house",
+            PokerHand::FourOfAKind => "Four of a kind",
+            PokerHand::Flush => "Flush",
+        }
+    }
+
     pub fn game_over(score: u32, level: u8) -> String {
         format!(
             "Game over! You scored {} points and got to level {}.",
@@ -64,61 +273,84 @@ pub mod strings {
 }
 
 pub mod gems {
+    use crate::theme::{self, cells, colorblind::ColorblindMode, Theme};
     use cmdjewel_core::gems::{Gem, GemColor};
-    use cursive::style::{Color, ColorStyle};
+    use cursive::style::ColorStyle;
 
-    /// Gets a printable string from a game::Gems.
+    /// Gets a printable string from a game::Gems, using the current glyph set.
     /// This doesn't belong in board as that file only contains game logic and nothing user-facing.
     pub fn gem_string(gem: Gem) -> String {
+        theme::glyphs().gem(gem).into()
+    }
+
+    /// Gets the row of gems shown above the logo.
+    pub fn logo_gems() -> String {
+        let gems = [
+            GemColor::White,
+            GemColor::Yellow,
+            GemColor::Green,
+            GemColor::Blue,
+            GemColor::Red,
+            GemColor::Orange,
+            GemColor::Purple,
+        ]
+        .map(|color| gem_string(Gem::Normal(color)));
+        format!("\n       {}\n", gems.join("   "))
+    }
+
+    /// Gets a letter marking the color of a special gem, so that it can be told apart without relying on hue.
+    /// Only used in colorblind mode--otherwise this is a space.
+    pub fn gem_marker(gem: Gem) -> char {
+        if theme::colorblind() == ColorblindMode::Off {
+            return ' ';
+        }
         match gem {
-            Gem::Empty => "•",
-            Gem::Normal(x) => match x {
-                GemColor::Blue => "▼",
-                GemColor::White => "●",
-                GemColor::Red => "■",
-                GemColor::Yellow => "◆",
-                GemColor::Green => "⬟",
-                GemColor::Orange => "⬢",
-                GemColor::Purple => "▲",
+            Gem::Flame(x) | Gem::Star(x) | Gem::Supernova(x) => match x {
+                GemColor::Blue => 'b',
+                GemColor::White => 'w',
+                GemColor::Red => 'r',
+                GemColor::Yellow => 'y',
+                GemColor::Green => 'g',
+                GemColor::Orange => 'o',
+                GemColor::Purple => 'p',
             },
-            Gem::Flame(x) => match x {
-                GemColor::Blue => "▽",
-                GemColor::White => "○",
-                GemColor::Red => "□",
-                GemColor::Yellow => "◇",
-                GemColor::Green => "⬠",
-                GemColor::Orange => "⬡",
-                GemColor::Purple => "△",
-            },
-            Gem::Star(_) => "★",
-            Gem::Supernova(_) => "☆",
-            Gem::Hypercube(_) => "◩",
+            _ => ' ',
         }
-        .into()
+    }
+
+    /// Gets the text for a board cell: a gem with a space on the left and its marker (if any) on the right.
+    pub fn gem_cell(gem: Gem) -> String {
+        format!(" {}{}", gem_string(gem), gem_marker(gem))
+    }
+
+    /// Gets the rows of a cell with a gem in it, at a scale. At 1x, this is just `gem_cell`.
+    pub fn gem_cell_rows(gem: Gem, scale: usize) -> Vec<String> {
+        cells::art(theme::glyphs(), gem, scale)
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                // Markers go in the top right
+                let marker = if i == 0 { gem_marker(gem) } else { ' ' };
+                format!(" {}{}", row, marker)
+            })
+            .collect()
     }
 
     /// Gets a ColorStyle given a game::Gems
     pub fn gem_color(gem: Gem) -> ColorStyle {
+        let theme = theme::current();
         match gem {
-            Gem::Empty => ColorStyle::new(Color::Rgb(67, 76, 94), Color::Rgb(46, 52, 64)),
-            Gem::Normal(x) => colorstyle_from_gemcolor(x),
-            Gem::Flame(x) => colorstyle_from_gemcolor(x),
-            Gem::Star(x) => colorstyle_from_gemcolor(x),
-            Gem::Supernova(x) => colorstyle_from_gemcolor(x),
-            Gem::Hypercube(_) => ColorStyle::new(Color::Rgb(213, 219, 230), Color::Rgb(67, 76, 94)),
+            Gem::Empty => ColorStyle::new(theme.muted.color(), theme.background.color()),
+            Gem::Normal(x) => colorstyle_from_gemcolor(&theme, x),
+            Gem::Flame(x) => colorstyle_from_gemcolor(&theme, x),
+            Gem::Star(x) => colorstyle_from_gemcolor(&theme, x),
+            Gem::Supernova(x) => colorstyle_from_gemcolor(&theme, x),
+            Gem::Hypercube(_) => ColorStyle::new(theme.primary.color(), theme.muted.color()),
         }
     }
 
     /// Returns a ColorStyle from a game::GemColor
-    fn colorstyle_from_gemcolor(gem_color: GemColor) -> ColorStyle {
-        match gem_color {
-            GemColor::Blue => ColorStyle::new(Color::Rgb(126, 158, 189), Color::Rgb(46, 52, 64)),
-            GemColor::White => ColorStyle::new(Color::Rgb(213, 219, 230), Color::Rgb(46, 52, 64)),
-            GemColor::Red => ColorStyle::new(Color::Rgb(190, 96, 105), Color::Rgb(46, 52, 64)),
-            GemColor::Yellow => ColorStyle::new(Color::Rgb(233, 201, 138), Color::Rgb(46, 52, 64)),
-            GemColor::Green => ColorStyle::new(Color::Rgb(162, 188, 139), Color::Rgb(46, 52, 64)),
-            GemColor::Orange => ColorStyle::new(Color::Rgb(207, 135, 111), Color::Rgb(46, 52, 64)),
-            GemColor::Purple => ColorStyle::new(Color::Rgb(174, 174, 255), Color::Rgb(46, 52, 64)),
-        }
+    fn colorstyle_from_gemcolor(theme: &Theme, gem_color: GemColor) -> ColorStyle {
+        ColorStyle::new(theme.gems.get(gem_color).color(), theme.background.color())
     }
 }
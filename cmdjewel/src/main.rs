@@ -1,54 +1,43 @@
 mod animations;
+mod audio;
+mod cli;
 mod config;
 mod constants;
+mod theme;
 mod ui;
 mod view;
 
-use cpal::traits::StreamTrait;
-use cursive::{
-    theme::{Color, ColorStyle},
-    traits::With,
-};
+use cursive::views::Dialog;
 
 fn main() {
+    let args = cli::Args::parse();
     let mut siv = cursive::default();
-    // Set a theme (nord) (I copy and pasted this from the git repo and changed some colors)
-    siv.set_theme(cursive::theme::Theme {
-        shadow: false,
-        borders: cursive::theme::BorderStyle::Simple,
-        palette: cursive::theme::Palette::retro().with(|palette| {
-            use cursive::style::PaletteColor::*;
-
-            palette[Background] = Color::Rgb(46, 52, 64);
-            palette[View] = Color::Rgb(46, 52, 64);
-            palette[Primary] = Color::Rgb(213, 219, 230);
-            palette[HighlightText] = Color::Rgb(213, 219, 230);
-            palette[TitlePrimary] = Color::Rgb(126, 158, 189);
-            palette[Secondary] = Color::Rgb(76, 86, 106);
-            palette[Tertiary] = Color::Rgb(59, 66, 82);
-            palette[Highlight] = Color::Rgb(190, 96, 105);
-            palette[HighlightInactive] = Color::Rgb(126, 158, 189);
-            palette[cursive::style::PaletteStyle::EditableText].color = ColorStyle::primary();
-        }),
-    });
-    // Sets the terminal background--uses an ANSI escape sequence to run an Operating System Command (OSC)
-    // that gets picked up by your terminal emulator. XTerm (and other emulators) implemented this
-    // as a command to change the background.
-    println!("\x1b]11;#2E3440\x07");
-    // Set up music
-    it2play_rs::load_bytes(
-        Vec::from(include_bytes!("../cmdjewel.it")),
-        it2play_rs::IT2Driver::HQ,
-    );
-    let stream = it2play_rs::generate_stream();
-    stream.play().unwrap();
-    it2play_rs::play(0);
-    it2play_rs::set_global_volume((config::load_config().settings.music_vol * 128.) as u16);
+    let settings = config::load_config().settings;
+    // Work out how to draw things (arguments take precedence over settings)
+    theme::set_glyphs(args.glyphs.unwrap_or(settings.glyphs));
+    theme::set_color_depth(args.colors.unwrap_or(settings.color_depth));
+    // Set a theme (this also sets the terminal background, on terminals that support it)
+    theme::apply(&mut siv, theme::find(&settings.theme));
+    theme::set_colorblind(settings.colorblind);
+    // Set up music (the handle has to live until we quit, or else the music stops). If the custom module can't be read
+    // anymore, the bundled soundtrack plays instead.
+    let custom_module = settings
+        .music
+        .custom_module
+        .as_deref()
+        .map(audio::read_module);
+    let _audio = audio::init(custom_module.clone().and_then(Result::ok));
+    audio::set_volume(settings.music_vol);
+    audio::set_sfx_volume(settings.sfx_vol);
     // Set title
     siv.set_window_title("cmdjewel");
     // show the start screen
     ui::show_menu_splash(&mut siv);
-    // set up commands
+    if let Some(Err(e)) = custom_module {
+        siv.add_layer(Dialog::info(constants::strings::custom_module_error(&e)));
+    }
+    // set up commands (debug ones only work with --debug)
+    ui::commands::set_debug(args.debug);
     ui::init_commands(&mut siv);
     // Set the refresh rate to 30 FPS and run
     siv.set_autorefresh(true);
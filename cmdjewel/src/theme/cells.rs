@@ -0,0 +1,141 @@
+// How big each cell on the board is drawn. Small cells are a single glyph, and bigger ones draw each gem with box art.
+
+use crate::theme::glyphs::GlyphSet;
+use cmdjewel_core::gems::{Gem, GemColor};
+use cursive::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Size of each cell on the board
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CellSize {
+    /// The biggest size that fits in the terminal
+    #[default]
+    Auto,
+    Small,
+    Medium,
+    Large,
+}
+
+impl CellSize {
+    /// Every cell size, in the order they're shown in settings
+    pub const ALL: [CellSize; 4] = [
+        CellSize::Auto,
+        CellSize::Small,
+        CellSize::Medium,
+        CellSize::Large,
+    ];
+
+    /// Gets how many times bigger than a small cell this is, or None when it's decided by the terminal size.
+    pub fn scale(&self) -> Option<usize> {
+        match self {
+            CellSize::Auto => None,
+            CellSize::Small => Some(1),
+            CellSize::Medium => Some(2),
+            CellSize::Large => Some(3),
+        }
+    }
+
+    /// Picks a scale for a board `width` cells across, so that it fits in `available` characters.
+    pub fn fit(&self, width: usize, available: Vec2) -> usize {
+        self.scale().unwrap_or_else(|| {
+            (1..=3)
+                .rev()
+                .find(|&scale| (cell(scale) * width).fits_in(available))
+                .unwrap_or(1)
+        })
+    }
+}
+
+impl std::fmt::Display for CellSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellSize::Auto => write!(f, "Auto"),
+            CellSize::Small => write!(f, "1x"),
+            CellSize::Medium => write!(f, "2x"),
+            CellSize::Large => write!(f, "3x"),
+        }
+    }
+}
+
+/// Gets how many characters across and down a cell is at a scale.
+/// Every cell has a column of space on the left, and one on the right for colorblind markers.
+pub fn cell(scale: usize) -> Vec2 {
+    Vec2::new(scale * 3, scale)
+}
+
+/// Gets the art for a gem at a scale, as rows that are two characters narrower than the cell.
+pub fn art(glyphs: GlyphSet, gem: Gem, scale: usize) -> Vec<String> {
+    let rows: &[&str] = match (glyphs, scale) {
+        (_, 0 | 1) => return vec![glyphs.gem(gem).to_string()],
+        (GlyphSet::Unicode, 2) => medium(gem),
+        (GlyphSet::Unicode, _) => large(gem),
+        // Other glyph sets don't have anything to draw art with, so gems are made out of their glyph
+        (_, scale) => {
+            let glyph = glyphs.gem(gem);
+            let width = cell(scale).x - 2;
+            return (0..scale)
+                .map(|_| match gem {
+                    Gem::Empty => format!("{:^width$}", glyph),
+                    _ => glyph.repeat(width),
+                })
+                .collect();
+        }
+    };
+    rows.iter().map(|row| row.to_string()).collect()
+}
+
+/// Art for 2x cells. Flame gems are outlines of normal ones.
+fn medium(gem: Gem) -> &'static [&'static str] {
+    match gem {
+        Gem::Empty => &["    ", " •  "],
+        Gem::Normal(x) => match x {
+            GemColor::Blue => &["▜██▛", " ▜▛ "],
+            GemColor::White => &["▗██▖", "▝██▘"],
+            GemColor::Red => &["████", "████"],
+            GemColor::Yellow => &[" ◢◣ ", " ◥◤ "],
+            GemColor::Green => &["▗▟▙▖", " ██ "],
+            GemColor::Orange => &["▟██▙", "▜██▛"],
+            GemColor::Purple => &[" ▟▙ ", "▟██▙"],
+        },
+        Gem::Flame(x) => match x {
+            GemColor::Blue => &["╲──╱", " ╲╱ "],
+            GemColor::White => &["╭──╮", "╰──╯"],
+            GemColor::Red => &["┌──┐", "└──┘"],
+            GemColor::Yellow => &[" ╱╲ ", " ╲╱ "],
+            GemColor::Green => &["╭╱╲╮", "╰──╯"],
+            GemColor::Orange => &["╱──╲", "╲──╱"],
+            GemColor::Purple => &[" ╱╲ ", "╱──╲"],
+        },
+        Gem::Star(_) => &["▚██▞", "▞██▚"],
+        Gem::Supernova(_) => &["╲┃┃╱", "╱┃┃╲"],
+        Gem::Hypercube(_) => &["▛▀▀▜", "▙▄▄▟"],
+    }
+}
+
+/// Art for 3x cells.
+fn large(gem: Gem) -> &'static [&'static str] {
+    match gem {
+        Gem::Empty => &["       ", "   •   ", "       "],
+        Gem::Normal(x) => match x {
+            GemColor::Blue => &["▜█████▛", " ▜███▛ ", "  ▜█▛  "],
+            GemColor::White => &["▗▟███▙▖", "███████", "▝▜███▛▘"],
+            GemColor::Red => &["▐█████▌", "▐█████▌", "▐█████▌"],
+            GemColor::Yellow => &["  ▟█▙  ", "▐█████▌", "  ▜█▛  "],
+            GemColor::Green => &["  ▟█▙  ", "▟█████▙", " ▜███▛ "],
+            GemColor::Orange => &[" ▟███▙ ", "▐█████▌", " ▜███▛ "],
+            GemColor::Purple => &["  ▟█▙  ", " ▟███▙ ", "▟█████▙"],
+        },
+        Gem::Flame(x) => match x {
+            GemColor::Blue => &["╲─────╱", " ╲   ╱ ", "  ╲ ╱  "],
+            GemColor::White => &["╭─────╮", "│     │", "╰─────╯"],
+            GemColor::Red => &["┌─────┐", "│     │", "└─────┘"],
+            GemColor::Yellow => &["  ╱ ╲  ", "  │ │  ", "  ╲ ╱  "],
+            GemColor::Green => &["  ╱─╲  ", " │   │ ", " └───┘ "],
+            GemColor::Orange => &[" ╱───╲ ", " │   │ ", " ╲───╱ "],
+            GemColor::Purple => &["  ╱ ╲  ", " ╱   ╲ ", "╱─────╲"],
+        },
+        Gem::Star(_) => &["▚▖ █ ▗▞", "███████", "▞▘ █ ▝▚"],
+        Gem::Supernova(_) => &["╲  ┃  ╱", "━━━╋━━━", "╱  ┃  ╲"],
+        Gem::Hypercube(_) => &["▛▀▀▀▀▀▜", "▌ ▓▓▓ ▐", "▙▄▄▄▄▄▟"],
+    }
+}
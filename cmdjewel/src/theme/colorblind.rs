@@ -0,0 +1,76 @@
+// Gem colors for people with color vision deficiencies. These replace the gem colors of whatever theme is being
+// used, and special gems get a marker so their color can be told apart without relying on hue at all.
+
+use crate::theme::{GemPalette, Rgb};
+use serde::{Deserialize, Serialize};
+
+/// Kinds of colorblindness cmdjewel has palettes for
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorblindMode {
+    #[default]
+    Off,
+    /// Red-green colorblindness where green looks dull
+    Deuteranopia,
+    /// Red-green colorblindness where red looks dark
+    Protanopia,
+    /// Blue-yellow colorblindness
+    Tritanopia,
+}
+
+impl ColorblindMode {
+    /// Every mode, in the order they're shown in settings
+    pub const ALL: [ColorblindMode; 4] = [
+        ColorblindMode::Off,
+        ColorblindMode::Deuteranopia,
+        ColorblindMode::Protanopia,
+        ColorblindMode::Tritanopia,
+    ];
+
+    /// Gets the gem colors to use instead of the theme's, if any.
+    pub fn palette(&self) -> Option<GemPalette> {
+        match self {
+            ColorblindMode::Off => None,
+            // Based on the Okabe-Ito palette: colors that differ in brightness as well as hue
+            ColorblindMode::Deuteranopia => Some(GemPalette {
+                blue: Rgb(86, 180, 233),
+                white: Rgb(240, 240, 240),
+                red: Rgb(213, 94, 0),
+                yellow: Rgb(240, 228, 66),
+                green: Rgb(0, 158, 115),
+                orange: Rgb(230, 159, 0),
+                purple: Rgb(204, 121, 167),
+            }),
+            // Like deuteranopia, but reds are brightened since they look darker with protanopia
+            ColorblindMode::Protanopia => Some(GemPalette {
+                blue: Rgb(86, 180, 233),
+                white: Rgb(240, 240, 240),
+                red: Rgb(255, 110, 58),
+                yellow: Rgb(240, 228, 66),
+                green: Rgb(0, 158, 115),
+                orange: Rgb(230, 159, 0),
+                purple: Rgb(220, 140, 200),
+            }),
+            // Keeps colors apart along the red-green axis, since blues and yellows get mixed up
+            ColorblindMode::Tritanopia => Some(GemPalette {
+                blue: Rgb(0, 119, 187),
+                white: Rgb(240, 240, 240),
+                red: Rgb(204, 51, 17),
+                yellow: Rgb(238, 119, 51),
+                green: Rgb(0, 153, 136),
+                orange: Rgb(255, 170, 150),
+                purple: Rgb(170, 51, 119),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorblindMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorblindMode::Off => write!(f, "Off"),
+            ColorblindMode::Deuteranopia => write!(f, "Deuteranopia"),
+            ColorblindMode::Protanopia => write!(f, "Protanopia"),
+            ColorblindMode::Tritanopia => write!(f, "Tritanopia"),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// How many colors the terminal can show. Themes are written in 24-bit color, so on terminals that can't show
+// that many, every color gets approximated.
+
+use crate::theme::Rgb;
+use cursive::style::{BaseColor, Color};
+use serde::{Deserialize, Serialize};
+
+/// How many colors to draw with
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorDepth {
+    /// Figure it out from the environment
+    #[default]
+    Auto,
+    /// 24-bit color
+    TrueColor,
+    /// The xterm 256 color palette
+    Colors256,
+    /// The 16 basic ANSI colors
+    Colors16,
+}
+
+/// The 16 ANSI colors, with the values xterm uses for them
+const ANSI_COLORS: [(Color, Rgb); 16] = [
+    (Color::Dark(BaseColor::Black), Rgb(0, 0, 0)),
+    (Color::Dark(BaseColor::Red), Rgb(205, 0, 0)),
+    (Color::Dark(BaseColor::Green), Rgb(0, 205, 0)),
+    (Color::Dark(BaseColor::Yellow), Rgb(205, 205, 0)),
+    (Color::Dark(BaseColor::Blue), Rgb(0, 0, 238)),
+    (Color::Dark(BaseColor::Magenta), Rgb(205, 0, 205)),
+    (Color::Dark(BaseColor::Cyan), Rgb(0, 205, 205)),
+    (Color::Dark(BaseColor::White), Rgb(229, 229, 229)),
+    (Color::Light(BaseColor::Black), Rgb(127, 127, 127)),
+    (Color::Light(BaseColor::Red), Rgb(255, 0, 0)),
+    (Color::Light(BaseColor::Green), Rgb(0, 255, 0)),
+    (Color::Light(BaseColor::Yellow), Rgb(255, 255, 0)),
+    (Color::Light(BaseColor::Blue), Rgb(92, 92, 255)),
+    (Color::Light(BaseColor::Magenta), Rgb(255, 0, 255)),
+    (Color::Light(BaseColor::Cyan), Rgb(0, 255, 255)),
+    (Color::Light(BaseColor::White), Rgb(255, 255, 255)),
+];
+
+impl ColorDepth {
+    /// Every color depth, in the order they're shown in settings
+    pub const ALL: [ColorDepth; 4] = [
+        ColorDepth::Auto,
+        ColorDepth::TrueColor,
+        ColorDepth::Colors256,
+        ColorDepth::Colors16,
+    ];
+
+    /// Turns Auto into the color depth the terminal supports, going by $COLORTERM and $TERM.
+    pub fn resolve(self) -> ColorDepth {
+        if self != ColorDepth::Auto {
+            return self;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Colors256
+        } else {
+            ColorDepth::Colors16
+        }
+    }
+
+    /// Converts a color into the closest one this color depth can show.
+    pub fn convert(self, color: Rgb) -> Color {
+        match self.resolve() {
+            ColorDepth::Colors256 => {
+                // Each channel goes from 0 to 5 in the 256 color cube
+                let channel = |c: u8| ((c as u16 * 5 + 127) / 255) as u8;
+                Color::RgbLowRes(channel(color.0), channel(color.1), channel(color.2))
+            }
+            ColorDepth::Colors16 => {
+                let distance = |other: &Rgb| {
+                    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                    d(color.0, other.0) + d(color.1, other.1) + d(color.2, other.2)
+                };
+                ANSI_COLORS
+                    .iter()
+                    .min_by_key(|(_, rgb)| distance(rgb))
+                    .map(|&(c, _)| c)
+                    .unwrap()
+            }
+            _ => Color::Rgb(color.0, color.1, color.2),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorDepth::Auto),
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Colors256),
+            "16" => Ok(ColorDepth::Colors16),
+            _ => Err(format!(
+                "unknown color depth \"{}\" (try auto, truecolor, 256, or 16)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorDepth::Auto => write!(f, "Auto"),
+            ColorDepth::TrueColor => write!(f, "24-bit"),
+            ColorDepth::Colors256 => write!(f, "256 colors"),
+            ColorDepth::Colors16 => write!(f, "16 colors"),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// Sets of characters gems can be drawn with, for terminals and fonts that can't show every Unicode shape.
+
+use cmdjewel_core::gems::{Gem, GemColor};
+use cmdjewel_core::point::Direction;
+use serde::{Deserialize, Serialize};
+
+/// Characters to draw gems with
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GlyphSet {
+    /// Geometric shapes, one for each color
+    #[default]
+    Unicode,
+    /// Symbols that are in plain ASCII, so they work with any font
+    Ascii,
+    /// The first letter of each gem's color
+    Letters,
+}
+
+impl GlyphSet {
+    /// Every glyph set, in the order they're shown in settings
+    pub const ALL: [GlyphSet; 3] = [GlyphSet::Unicode, GlyphSet::Ascii, GlyphSet::Letters];
+
+    /// Gets the character for a gem.
+    pub fn gem(&self, gem: Gem) -> &'static str {
+        match self {
+            GlyphSet::Unicode => match gem {
+                Gem::Empty => "•",
+                Gem::Normal(x) => match x {
+                    GemColor::Blue => "▼",
+                    GemColor::White => "●",
+                    GemColor::Red => "■",
+                    GemColor::Yellow => "◆",
+                    GemColor::Green => "⬟",
+                    GemColor::Orange => "⬢",
+                    GemColor::Purple => "▲",
+                },
+                Gem::Flame(x) => match x {
+                    GemColor::Blue => "▽",
+                    GemColor::White => "○",
+                    GemColor::Red => "□",
+                    GemColor::Yellow => "◇",
+                    GemColor::Green => "⬠",
+                    GemColor::Orange => "⬡",
+                    GemColor::Purple => "△",
+                },
+                Gem::Star(_) => "★",
+                Gem::Supernova(_) => "☆",
+                Gem::Hypercube(_) => "◩",
+            },
+            GlyphSet::Ascii => match gem {
+                Gem::Empty => ".",
+                Gem::Normal(x) => match x {
+                    GemColor::Blue => "v",
+                    GemColor::White => "o",
+                    GemColor::Red => "#",
+                    GemColor::Yellow => "$",
+                    GemColor::Green => "&",
+                    GemColor::Orange => "@",
+                    GemColor::Purple => "^",
+                },
+                // "Hollow" versions of the above, where there's a fitting one
+                Gem::Flame(x) => match x {
+                    GemColor::Blue => "V",
+                    GemColor::White => "O",
+                    GemColor::Red => "H",
+                    GemColor::Yellow => "S",
+                    GemColor::Green => "8",
+                    GemColor::Orange => "Q",
+                    GemColor::Purple => "A",
+                },
+                Gem::Star(_) => "*",
+                Gem::Supernova(_) => "+",
+                Gem::Hypercube(_) => "?",
+            },
+            GlyphSet::Letters => match gem {
+                Gem::Empty => ".",
+                // Normal gems are lowercase, and flame gems are uppercase
+                Gem::Normal(x) => match x {
+                    GemColor::Blue => "b",
+                    GemColor::White => "w",
+                    GemColor::Red => "r",
+                    GemColor::Yellow => "y",
+                    GemColor::Green => "g",
+                    GemColor::Orange => "o",
+                    GemColor::Purple => "p",
+                },
+                Gem::Flame(x) => match x {
+                    GemColor::Blue => "B",
+                    GemColor::White => "W",
+                    GemColor::Red => "R",
+                    GemColor::Yellow => "Y",
+                    GemColor::Green => "G",
+                    GemColor::Orange => "O",
+                    GemColor::Purple => "P",
+                },
+                Gem::Star(_) => "*",
+                Gem::Supernova(_) => "+",
+                Gem::Hypercube(_) => "?",
+            },
+        }
+    }
+
+    /// Gets an arrow pointing in a direction, used to show which way to swap a gem.
+    pub fn arrow(&self, direction: Direction) -> char {
+        match (self, direction) {
+            (GlyphSet::Unicode, Direction::Left) => '←',
+            (GlyphSet::Unicode, Direction::Right) => '→',
+            (GlyphSet::Unicode, Direction::Up) => '↑',
+            (GlyphSet::Unicode, Direction::Down) => '↓',
+            (_, Direction::Left) => '<',
+            (_, Direction::Right) => '>',
+            (_, Direction::Up) => '^',
+            (_, Direction::Down) => 'v',
+        }
+    }
+}
+
+impl std::str::FromStr for GlyphSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unicode" => Ok(GlyphSet::Unicode),
+            "ascii" => Ok(GlyphSet::Ascii),
+            "letters" => Ok(GlyphSet::Letters),
+            _ => Err(format!(
+                "unknown glyph set \"{}\" (try unicode, ascii, or letters)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for GlyphSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlyphSet::Unicode => write!(f, "Unicode"),
+            GlyphSet::Ascii => write!(f, "ASCII"),
+            GlyphSet::Letters => write!(f, "Letters"),
+        }
+    }
+}
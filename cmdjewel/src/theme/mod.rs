@@ -0,0 +1,283 @@
+// Color themes. Every color cmdjewel draws with comes from the current theme.
+
+pub mod cells;
+pub mod colorblind;
+pub mod depth;
+pub mod glyphs;
+
+use crate::constants;
+use cmdjewel_core::gems::GemColor;
+use colorblind::ColorblindMode;
+use cursive::style::Color;
+use cursive::traits::With;
+use cursive::Cursive;
+use depth::ColorDepth;
+use glyphs::GlyphSet;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+/// The theme that was last applied. None until a theme is applied, in which case the first bundled theme is used.
+static CURRENT: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+
+/// The current theme with colorblind mode's gem colors swapped in. This is what gets drawn with, and is rebuilt
+/// whenever either of them changes so drawing a frame doesn't have to.
+static DRAWN: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+
+/// Every theme that can be picked. None until the themes directory is first read.
+static THEMES: RwLock<Option<Vec<Arc<Theme>>>> = RwLock::new(None);
+
+/// Colorblind mode, which overrides the gem colors of whatever theme is current
+static COLORBLIND: RwLock<ColorblindMode> = RwLock::new(ColorblindMode::Off);
+
+/// How many colors the terminal can show. This is never Auto, since that gets resolved when it's set.
+static COLOR_DEPTH: RwLock<ColorDepth> = RwLock::new(ColorDepth::TrueColor);
+
+/// Characters gems are drawn with
+static GLYPHS: RwLock<GlyphSet> = RwLock::new(GlyphSet::Unicode);
+
+/// Themes that come with cmdjewel. The first one is the default.
+const BUNDLED: [&str; 4] = [
+    include_str!("../../themes/nord.toml"),
+    include_str!("../../themes/light.toml"),
+    include_str!("../../themes/solarized.toml"),
+    include_str!("../../themes/high_contrast.toml"),
+];
+
+/// A 24-bit color, written as "#RRGGBB" in theme files.
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or(format!("{} is not a color like #RRGGBB", value))
+        };
+        if hex.len() != 6 {
+            return Err(format!("{} is not a color like #RRGGBB", value));
+        }
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(value: Rgb) -> Self {
+        format!("#{:02X}{:02X}{:02X}", value.0, value.1, value.2)
+    }
+}
+
+impl Rgb {
+    /// Converts the color into something cursive can draw, approximating it if the terminal can't show 24-bit color.
+    pub fn color(self) -> Color {
+        COLOR_DEPTH.read().unwrap().convert(self)
+    }
+
+    /// Mixes this color with another one. An amount of 0 is this color, and 1 is the other one.
+    pub fn mix(self, other: Rgb, amount: f32) -> Rgb {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+        Rgb(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+        )
+    }
+}
+
+/// Colors for each gem
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GemPalette {
+    pub blue: Rgb,
+    pub white: Rgb,
+    pub red: Rgb,
+    pub yellow: Rgb,
+    pub green: Rgb,
+    pub orange: Rgb,
+    pub purple: Rgb,
+}
+
+impl GemPalette {
+    /// Gets the color of a gem
+    pub fn get(&self, color: GemColor) -> Rgb {
+        match color {
+            GemColor::Blue => self.blue,
+            GemColor::White => self.white,
+            GemColor::Red => self.red,
+            GemColor::Yellow => self.yellow,
+            GemColor::Green => self.green,
+            GemColor::Orange => self.orange,
+            GemColor::Purple => self.purple,
+        }
+    }
+}
+
+/// A color theme, as read from a theme file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub background: Rgb,
+    pub view: Rgb,
+    pub primary: Rgb,
+    pub secondary: Rgb,
+    pub tertiary: Rgb,
+    pub title: Rgb,
+    pub highlight: Rgb,
+    pub highlight_inactive: Rgb,
+    /// Used for empty spaces on the board and the background of hypercubes
+    pub muted: Rgb,
+    pub gems: GemPalette,
+}
+
+impl Theme {
+    /// Returns a copy of this theme with every color (except the gems) faded into the background by some amount
+    /// between 0 and 1.
+    pub fn faded(&self, amount: f32) -> Theme {
+        let fade = |color: Rgb| color.mix(self.background, amount);
+        Theme {
+            view: fade(self.view),
+            primary: fade(self.primary),
+            secondary: fade(self.secondary),
+            tertiary: fade(self.tertiary),
+            title: fade(self.title),
+            highlight: fade(self.highlight),
+            highlight_inactive: fade(self.highlight_inactive),
+            muted: fade(self.muted),
+            ..self.clone()
+        }
+    }
+
+    /// Creates a cursive theme out of this one.
+    pub fn to_cursive(&self) -> cursive::theme::Theme {
+        cursive::theme::Theme {
+            shadow: false,
+            borders: cursive::theme::BorderStyle::Simple,
+            palette: cursive::theme::Palette::retro().with(|palette| {
+                use cursive::style::PaletteColor::*;
+
+                palette[Background] = self.background.color();
+                palette[View] = self.view.color();
+                palette[Primary] = self.primary.color();
+                palette[HighlightText] = self.primary.color();
+                palette[TitlePrimary] = self.title.color();
+                palette[Secondary] = self.secondary.color();
+                palette[Tertiary] = self.tertiary.color();
+                palette[Highlight] = self.highlight.color();
+                palette[HighlightInactive] = self.highlight_inactive.color();
+                palette[cursive::style::PaletteStyle::EditableText].color =
+                    cursive::theme::ColorStyle::primary();
+            }),
+        }
+    }
+}
+
+/// Gets every bundled theme, followed by any themes in the config directory.
+/// The themes directory is only read the first time, so new theme files show up the next time cmdjewel starts.
+pub fn themes() -> Vec<Arc<Theme>> {
+    if let Some(themes) = THEMES.read().unwrap().as_ref() {
+        return themes.clone();
+    }
+    let themes: Vec<Arc<Theme>> = read_themes().into_iter().map(Arc::new).collect();
+    *THEMES.write().unwrap() = Some(themes.clone());
+    themes
+}
+
+/// Reads the bundled themes and the themes directory. Theme files that can't be read are skipped.
+fn read_themes() -> Vec<Theme> {
+    let mut themes: Vec<Theme> = BUNDLED
+        .iter()
+        .map(|t| toml::from_str(t).expect("bundled themes should be valid"))
+        .collect();
+    if let Some(dir) = crate::config::config_path().map(|p| p.with_file_name(constants::THEMES_DIR))
+    {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_default();
+        paths.sort();
+        paths
+            .iter()
+            .filter(|path| path.extension().is_some_and(|e| e == "toml"))
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .filter_map(|data| toml::from_str(&data).ok())
+            .for_each(|theme| themes.push(theme));
+    }
+    themes
+}
+
+/// Finds a theme by its name, or returns the default theme if there isn't one.
+pub fn find(name: &str) -> Arc<Theme> {
+    let mut themes = themes();
+    let i = themes.iter().position(|t| t.name == name).unwrap_or(0);
+    themes.swap_remove(i)
+}
+
+/// Returns the current theme, with gem colors swapped out if colorblind mode is on.
+pub fn current() -> Arc<Theme> {
+    if let Some(theme) = DRAWN.read().unwrap().as_ref() {
+        return theme.clone();
+    }
+    refresh()
+}
+
+/// Rebuilds the theme that gets drawn with from the current theme and colorblind mode, and returns it.
+fn refresh() -> Arc<Theme> {
+    let base = CURRENT.read().unwrap().clone().unwrap_or_else(|| {
+        Arc::new(toml::from_str(BUNDLED[0]).expect("bundled themes should be valid"))
+    });
+    let theme = match colorblind().palette() {
+        Some(gems) => Arc::new(Theme {
+            gems,
+            ..(*base).clone()
+        }),
+        None => base,
+    };
+    *DRAWN.write().unwrap() = Some(theme.clone());
+    theme
+}
+
+/// Gets the current colorblind mode.
+pub fn colorblind() -> ColorblindMode {
+    *COLORBLIND.read().unwrap()
+}
+
+/// Sets the colorblind mode. Gems are drawn with the new colors from the next frame on.
+pub fn set_colorblind(mode: ColorblindMode) {
+    *COLORBLIND.write().unwrap() = mode;
+    refresh();
+}
+
+/// Gets the current glyph set.
+pub fn glyphs() -> GlyphSet {
+    *GLYPHS.read().unwrap()
+}
+
+/// Sets the glyph set gems are drawn with.
+pub fn set_glyphs(glyphs: GlyphSet) {
+    *GLYPHS.write().unwrap() = glyphs;
+}
+
+/// Sets how many colors to draw with. Themes have to be applied again for this to change anything outside of the board.
+pub fn set_color_depth(depth: ColorDepth) {
+    *COLOR_DEPTH.write().unwrap() = depth.resolve();
+}
+
+/// Makes a theme the current one, and redraws everything with it.
+pub fn apply(s: &mut Cursive, theme: Arc<Theme>) {
+    s.set_theme(theme.to_cursive());
+    set_terminal_background(theme.background);
+    *CURRENT.write().unwrap() = Some(theme);
+    refresh();
+}
+
+/// Sets the terminal background--uses an ANSI escape sequence to run an Operating System Command (OSC)
+/// that gets picked up by your terminal emulator. XTerm (and other emulators) implemented this
+/// as a command to change the background. This can happen while cursive is drawing, so it doesn't print a newline
+/// (which would move cursive's cursor).
+pub fn set_terminal_background(color: Rgb) {
+    print!("\x1b]11;{}\x07", String::from(color));
+    std::io::stdout().flush().unwrap_or_default();
+}
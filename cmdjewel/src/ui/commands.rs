@@ -0,0 +1,559 @@
+// The command console, opened with ':'. Every command is registered in COMMANDS along with its arguments and help
+// text, so adding a command is just adding an entry there.
+
+use crate::config;
+use crate::constants::strings;
+use crate::ui::{show_game, show_menu_main};
+use crate::view::BoardView;
+use cmdjewel_core::board::BoardConfig;
+use cmdjewel_core::notation;
+use cmdjewel_core::point::Point;
+use cursive::event::{Event, Key};
+use cursive::view::{Nameable, Resizable};
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, TextView};
+use cursive::Cursive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// What an argument can be
+pub enum ArgKind {
+    /// One of a few words
+    Choice(&'static [&'static str]),
+    /// The name of a command
+    Command,
+    /// A whole number
+    Number,
+    /// Any word
+    Word,
+}
+
+/// An argument a command takes
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    /// Optional arguments can be left off the end of a command
+    pub optional: bool,
+}
+
+/// A value typed in for an argument
+pub enum Value {
+    Word(String),
+    Number(i64),
+}
+
+/// A command that can be typed into the console
+pub struct Command {
+    pub name: &'static str,
+    /// Other names for the command, like shorthands
+    pub aliases: &'static [&'static str],
+    /// Debug commands only exist when cmdjewel is started with --debug
+    pub debug: bool,
+    pub args: &'static [Arg],
+    pub help: &'static str,
+    /// Runs the command, given a value for each argument that was typed (in order)
+    pub run: fn(&mut Cursive, &[Value]) -> Result<(), String>,
+}
+
+/// Every command, in the order they're listed by :help
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        aliases: &[],
+        debug: false,
+        args: &[Arg {
+            name: "command",
+            kind: ArgKind::Command,
+            optional: true,
+        }],
+        help: "Lists every command, or explains one.",
+        run: help,
+    },
+    Command {
+        name: "main",
+        aliases: &["m"],
+        debug: false,
+        args: &[],
+        help: "Goes to the main menu.",
+        run: |s, _| {
+            show_menu_main(s);
+            Ok(())
+        },
+    },
+    Command {
+        name: "play",
+        aliases: &["p"],
+        debug: false,
+        args: &[Arg {
+            name: "mode",
+            kind: ArgKind::Choice(&["classic", "zen", "poker", "icestorm"]),
+            optional: true,
+        }],
+        help: "Starts a game (Classic, unless you pick a mode).",
+        run: |s, values| {
+            let config = match word(values, 0) {
+                Some("zen") => BoardConfig::new_zen(),
+                Some("poker") => BoardConfig::new_poker(),
+                Some("icestorm") => BoardConfig::new_ice_storm(),
+                _ => BoardConfig::new_classic(),
+            };
+            show_game(s, config);
+            Ok(())
+        },
+    },
+    Command {
+        name: "hint",
+        aliases: &["h"],
+        debug: false,
+        args: &[],
+        help: "Gives a hint.",
+        run: |s, _| board(s, |b| b.hint()),
+    },
+    Command {
+        name: "quit",
+        aliases: &["q", "qa"],
+        debug: false,
+        args: &[],
+        help: "Saves the game and quits.",
+        run: |s, _| {
+            s.call_on_name("board", |b: &mut BoardView| {
+                config::save_board(&b.board, false)
+            })
+            .unwrap_or_default();
+            s.quit();
+            Ok(())
+        },
+    },
+    Command {
+        name: "quit!",
+        aliases: &["q!", "qa!"],
+        debug: false,
+        args: &[],
+        help: "Quits without saving.",
+        run: |s, _| {
+            s.quit();
+            Ok(())
+        },
+    },
+    // Animation debugging
+    Command {
+        name: "explode",
+        aliases: &[],
+        debug: false,
+        args: &[],
+        help: "Plays the game over animation.",
+        run: |s, _| board(s, |b| b.animation_explode()),
+    },
+    Command {
+        name: "warp",
+        aliases: &[],
+        debug: false,
+        args: &[],
+        help: "Plays the level up animation.",
+        run: |s, _| board(s, |b| b.animation_warp()),
+    },
+    // Other debugging
+    Command {
+        name: "autoplay",
+        aliases: &[],
+        debug: false,
+        args: &[],
+        help: "Toggles the game playing itself.",
+        run: |s, _| board(s, |b| b.autoplay = !b.autoplay),
+    },
+    Command {
+        name: "noanims",
+        aliases: &[],
+        debug: false,
+        args: &[],
+        help: "Toggles animations.",
+        run: |s, _| board(s, |b| b.animations_enabled = !b.animations_enabled),
+    },
+    // Board manipulation, for testing
+    Command {
+        name: "set",
+        aliases: &[],
+        debug: true,
+        args: &[
+            Arg {
+                name: "x",
+                kind: ArgKind::Number,
+                optional: false,
+            },
+            Arg {
+                name: "y",
+                kind: ArgKind::Number,
+                optional: false,
+            },
+            Arg {
+                name: "gem",
+                kind: ArgKind::Word,
+                optional: false,
+            },
+        ],
+        help: "Puts a gem somewhere, like r, B (a flame gem), *g (a star gem), or ? (a hypercube). (0, 0) is the top left.",
+        run: |s, values| {
+            let x = number(values, 0, "x", (0, 7))?.unwrap_or_default();
+            let y = number(values, 1, "y", (0, 7))?.unwrap_or_default();
+            let gem = notation::parse_gem(word(values, 2).unwrap_or_default())?;
+            board(s, |b| b.edit(|board| board.set_gem(Point(x, y), gem)))
+        },
+    },
+    Command {
+        name: "fill",
+        aliases: &[],
+        debug: true,
+        args: &[Arg {
+            name: "board",
+            kind: ArgKind::Word,
+            optional: false,
+        }],
+        help: "Replaces the board, written as 8 rows of gems separated by / (see :help set for how gems are written).",
+        run: |s, values| {
+            let data = notation::parse(word(values, 0).unwrap_or_default())?;
+            board(s, |b| b.edit(|board| board.set_data(data)))
+        },
+    },
+    Command {
+        name: "shuffle",
+        aliases: &[],
+        debug: true,
+        args: &[],
+        help: "Shuffles the board.",
+        run: |s, _| {
+            board(s, |b| {
+                b.edit(|board| {
+                    board.shuffle();
+                })
+            })
+        },
+    },
+    Command {
+        name: "score",
+        aliases: &[],
+        debug: true,
+        args: &[Arg {
+            name: "score",
+            kind: ArgKind::Number,
+            optional: false,
+        }],
+        help: "Sets the score.",
+        run: |s, values| {
+            let score = number(values, 0, "score", (0, u32::MAX as i64))?.unwrap_or_default();
+            board(s, |b| b.board.set_score(score))
+        },
+    },
+    Command {
+        name: "level",
+        aliases: &[],
+        debug: true,
+        args: &[Arg {
+            name: "level",
+            kind: ArgKind::Number,
+            optional: false,
+        }],
+        help: "Skips to the start of a level.",
+        run: |s, values| {
+            let level: u8 = number(values, 0, "level", (1, u8::MAX as i64))?.unwrap_or(1);
+            board(s, |b| b.board.set_level(level - 1))
+        },
+    },
+    Command {
+        name: "seed",
+        aliases: &[],
+        debug: true,
+        args: &[Arg {
+            name: "seed",
+            kind: ArgKind::Number,
+            optional: false,
+        }],
+        help: "Makes new gems come from a seed, so they're the same every time.",
+        run: |s, values| {
+            let seed = number(values, 0, "seed", (0, i64::MAX))?.unwrap_or_default();
+            board(s, |b| b.board.reseed(seed))
+        },
+    },
+];
+
+/// Runs something on the game board, or fails if there isn't a game going.
+fn board<T>(s: &mut Cursive, f: impl FnOnce(&mut BoardView) -> T) -> Result<T, String> {
+    s.call_on_name("board", f)
+        .ok_or_else(|| strings::NO_GAME.to_string())
+}
+
+/// Gets an argument's value as a word, if it was typed.
+pub fn word(values: &[Value], i: usize) -> Option<&str> {
+    match values.get(i) {
+        Some(Value::Word(word)) => Some(word),
+        _ => None,
+    }
+}
+
+/// Gets an argument's value as a number between `min` and `max`, if it was typed.
+pub fn number<T: TryFrom<i64>>(
+    values: &[Value],
+    i: usize,
+    name: &str,
+    (min, max): (i64, i64),
+) -> Result<Option<T>, String> {
+    match values.get(i) {
+        Some(&Value::Number(n)) if (min..=max).contains(&n) => Ok(T::try_from(n).ok()),
+        Some(&Value::Number(_)) => Err(strings::out_of_range(name, min, max)),
+        _ => Ok(None),
+    }
+}
+
+/// If debug commands are allowed
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+/// Allows (or stops allowing) debug commands.
+pub fn set_debug(debug: bool) {
+    DEBUG.store(debug, Ordering::Relaxed);
+}
+
+/// Gets every command that can be used right now.
+fn available() -> impl Iterator<Item = &'static Command> {
+    COMMANDS
+        .iter()
+        .filter(|c| !c.debug || DEBUG.load(Ordering::Relaxed))
+}
+
+/// Finds a command by its name or one of its aliases.
+fn find(name: &str) -> Option<&'static Command> {
+    available().find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+impl ArgKind {
+    /// Reads a value out of a word.
+    fn parse(&self, word: &str) -> Result<Value, String> {
+        match self {
+            ArgKind::Choice(choices) => {
+                let word = word.to_lowercase();
+                if choices.contains(&word.as_str()) {
+                    Ok(Value::Word(word))
+                } else {
+                    Err(strings::not_a_choice(&word, choices))
+                }
+            }
+            ArgKind::Command => {
+                let word = word.to_lowercase();
+                match find(&word) {
+                    Some(command) => Ok(Value::Word(command.name.to_string())),
+                    None => Err(strings::unknown_command(&word)),
+                }
+            }
+            ArgKind::Number => word
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| strings::not_a_number(word)),
+            ArgKind::Word => Ok(Value::Word(word.to_string())),
+        }
+    }
+
+    /// Gets every word this argument could be, for tab completion. Empty if it could be anything.
+    fn options(&self) -> Vec<&'static str> {
+        match self {
+            ArgKind::Choice(choices) => choices.to_vec(),
+            ArgKind::Command => available().map(|c| c.name).collect(),
+            ArgKind::Number | ArgKind::Word => Vec::new(),
+        }
+    }
+}
+
+impl Command {
+    /// Gets how to type this command, like `:play [classic|zen]`.
+    pub fn usage(&self) -> String {
+        self.args
+            .iter()
+            .fold(format!(":{}", self.name), |usage, arg| {
+                let name = match arg.kind {
+                    ArgKind::Choice(choices) => choices.join("|"),
+                    _ => arg.name.to_string(),
+                };
+                if arg.optional {
+                    format!("{} [{}]", usage, name)
+                } else {
+                    format!("{} <{}>", usage, name)
+                }
+            })
+    }
+}
+
+/// Reads a command and its arguments out of a line typed into the console.
+pub fn parse(line: &str) -> Result<(&'static Command, Vec<Value>), String> {
+    let mut words = line.split_whitespace();
+    // Be generous and lowercase command names
+    let name = words.next().unwrap_or_default().to_lowercase();
+    let command = find(&name).ok_or_else(|| strings::unknown_command(&name))?;
+    let words: Vec<&str> = words.collect();
+    if words.len() > command.args.len() {
+        return Err(strings::bad_usage(strings::TOO_MANY_ARGS, &command.usage()));
+    }
+    let mut values = Vec::new();
+    for (i, arg) in command.args.iter().enumerate() {
+        match words.get(i) {
+            Some(word) => values.push(
+                arg.kind
+                    .parse(word)
+                    .map_err(|e| strings::bad_usage(&e, &command.usage()))?,
+            ),
+            None if arg.optional => break,
+            None => {
+                return Err(strings::bad_usage(
+                    &strings::missing_arg(arg.name),
+                    &command.usage(),
+                ))
+            }
+        }
+    }
+    Ok((command, values))
+}
+
+/// Runs a line typed into the console.
+pub fn run(s: &mut Cursive, line: &str) -> Result<(), String> {
+    let (command, values) = parse(line)?;
+    (command.run)(s, &values)
+}
+
+/// Completes the last word of a line, as far as it can be without being ambiguous.
+pub fn complete(line: &str) -> String {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let partial = if line.ends_with(' ') {
+        ""
+    } else {
+        words.pop().unwrap_or_default()
+    };
+    // Either a command name, or one of the values its next argument can be
+    let options = match words.first() {
+        None => available()
+            .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+            .collect(),
+        Some(name) => find(&name.to_lowercase())
+            .and_then(|c| c.args.get(words.len() - 1))
+            .map(|arg| arg.kind.options())
+            .unwrap_or_default(),
+    };
+    let matches: Vec<&str> = options
+        .into_iter()
+        .filter(|o| o.starts_with(&partial.to_lowercase()))
+        .collect();
+    let completed = match matches.as_slice() {
+        [] => return line.to_string(),
+        [only] => format!("{} ", only),
+        [first, rest @ ..] => rest.iter().fold(first.to_string(), |prefix, m| {
+            prefix
+                .chars()
+                .zip(m.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+    words.push(&completed);
+    words.join(" ")
+}
+
+/// Shows every command, or help for one.
+fn help(s: &mut Cursive, values: &[Value]) -> Result<(), String> {
+    let text = match word(values, 0).and_then(find) {
+        Some(command) => {
+            let mut text = format!("{}\n{}", command.usage(), command.help);
+            if !command.aliases.is_empty() {
+                text += &strings::aliases(command.aliases);
+            }
+            text
+        }
+        None => available()
+            .map(|c| format!("{}\n  {}", c.usage(), c.help))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+    s.add_layer(Dialog::info(text).title(strings::COMMANDS));
+    Ok(())
+}
+
+/// Where the console is in the history, while going through it with up and down
+struct History {
+    lines: Vec<String>,
+    /// None when not looking at the history
+    position: Option<usize>,
+}
+
+impl History {
+    /// Moves through the history, and gets the line there. Going down past the newest line clears the console.
+    fn step(&mut self, up: bool) -> String {
+        self.position = match (self.position, up) {
+            (None, true) => self.lines.len().checked_sub(1),
+            (Some(p), true) => Some(p.saturating_sub(1)),
+            (Some(p), false) if p + 1 < self.lines.len() => Some(p + 1),
+            (_, false) => None,
+        };
+        self.position
+            .map(|p| self.lines[p].clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Shows the console, where commands can be typed.
+pub fn show_console(s: &mut Cursive) {
+    let history = Arc::new(Mutex::new(History {
+        lines: config::load_history(),
+        position: None,
+    }));
+    let mut edit_view = EditView::new().on_submit(submit);
+    edit_view.set_filler(" ");
+    // Up and down go through commands typed before, and tab completes them
+    let (up, down) = (history.clone(), history);
+    let console = OnEventView::new(edit_view.with_name("console"))
+        .on_pre_event(Event::Key(Key::Up), move |s| {
+            let line = up.lock().unwrap().step(true);
+            set_line(s, &line);
+        })
+        .on_pre_event(Event::Key(Key::Down), move |s| {
+            let line = down.lock().unwrap().step(false);
+            set_line(s, &line);
+        })
+        .on_pre_event(Event::Key(Key::Tab), |s| {
+            let line = s
+                .call_on_name("console", |e: &mut EditView| e.get_content())
+                .unwrap();
+            set_line(s, &complete(&line));
+        });
+    s.add_layer(
+        Dialog::new()
+            .title(strings::COMMAND)
+            .content(
+                OnEventView::new(
+                    LinearLayout::horizontal()
+                        .child(TextView::new("> "))
+                        .child(console.full_width()),
+                )
+                .on_event(Event::Key(Key::Esc), |s| {
+                    s.pop_layer();
+                }),
+            )
+            .with_name("command")
+            .fixed_width(32),
+    );
+}
+
+/// Replaces what's typed in the console.
+fn set_line(s: &mut Cursive, line: &str) {
+    s.call_on_name("console", |e: &mut EditView| e.set_content(line));
+}
+
+/// Closes the console, remembers the command, and runs it.
+fn submit(s: &mut Cursive, line: &str) {
+    s.pop_layer();
+    let line = line.trim();
+    if line.is_empty() {
+        return;
+    }
+    let mut history = config::load_history();
+    if history.last().map(String::as_str) != Some(line) {
+        history.push(line.to_string());
+        config::save_history(&history);
+    }
+    if let Err(error) = run(s, line) {
+        s.add_layer(Dialog::info(error));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::constants::{self, strings};
+use cmdjewel_core::gems::{Gem, GemColor};
+use cmdjewel_core::poker::{PokerHand, HAND_SIZE};
+use cursive::{Printer, Vec2, View};
+
+/// Width of a card (the same as a cell on the board, at 1x)
+const CARD_WIDTH: usize = 3;
+
+/// Shows a poker hand being dealt: a slot for each card, with a gem of its color once it's been dealt, and what the
+/// last full hand was worth.
+#[derive(Default)]
+pub struct HandView {
+    cards: Vec<GemColor>,
+    last: Option<(PokerHand, u32)>,
+}
+
+impl HandView {
+    /// Sets the cards dealt so far.
+    pub fn set_cards(&mut self, cards: Vec<GemColor>) {
+        self.cards = cards;
+    }
+
+    /// Sets the last hand that was scored, and how many points it was worth.
+    pub fn set_last(&mut self, last: Option<(PokerHand, u32)>) {
+        self.last = last;
+    }
+}
+
+impl View for HandView {
+    fn draw(&self, printer: &Printer) {
+        // Slots that haven't been dealt yet look like empty cells
+        for i in 0..HAND_SIZE {
+            let gem = self.cards.get(i).map_or(Gem::Empty, |&c| Gem::Normal(c));
+            printer.with_color(constants::gems::gem_color(gem), |printer| {
+                printer.print((i * CARD_WIDTH, 0), &constants::gems::gem_cell(gem))
+            });
+        }
+        if let Some((hand, points)) = self.last {
+            printer.print((1, 1), strings::poker_hand(hand));
+            printer.print((1, 2), &format!("+{}", points));
+        }
+    }
+
+    fn required_size(&mut self, _: Vec2) -> Vec2 {
+        Vec2::new(HAND_SIZE * CARD_WIDTH, 3)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::config::keys;
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    view::{CannotFocus, View},
+    Cursive, Printer, Vec2,
+};
+use std::sync::Arc;
+
+/// Called with the name of the key that was pressed, or `None` to clear a binding.
+type OnKey = Arc<dyn Fn(&mut Cursive, Option<String>) + Send + Sync>;
+
+/// Waits for a key to be pressed, and then calls a callback with the name of that key.
+///
+/// Esc cancels, and Backspace calls the callback with `None` (to clear a binding). That means neither can be captured;
+/// they can only be bound by picking a preset (they all pause with Esc) or by editing the config file.
+pub struct KeyCapture {
+    callback: OnKey,
+}
+
+impl KeyCapture {
+    /// Creates a new key capture with the given callback.
+    pub fn new<F>(cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Option<String>) + Send + Sync,
+    {
+        KeyCapture {
+            callback: Arc::new(cb),
+        }
+    }
+
+    fn call(&self, key: Option<String>) -> EventResult {
+        let callback = self.callback.clone();
+        EventResult::Consumed(Some(Callback::from_fn(move |s| callback(s, key.clone()))))
+    }
+}
+
+impl View for KeyCapture {
+    fn draw(&self, printer: &Printer) {
+        printer.print((0, 0), "...");
+    }
+
+    fn required_size(&mut self, _: Vec2) -> Vec2 {
+        Vec2::new(3, 1)
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Key(Key::Esc) => EventResult::with_cb(|s| {
+                s.pop_layer();
+            }),
+            Event::Key(Key::Backspace) => self.call(None),
+            event => match keys::describe_key(&event) {
+                Some(key) => self.call(Some(key)),
+                None => EventResult::Ignored,
+            },
+        }
+    }
+
+    fn take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
+        Ok(EventResult::Consumed(None))
+    }
+}
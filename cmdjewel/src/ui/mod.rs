@@ -1,21 +1,27 @@
+use crate::audio::Soundtrack;
+use crate::config::keys::Action;
 use crate::constants::strings;
+use crate::ui::hand_view::HandView;
 use crate::ui::multiline_button::Button;
 use crate::view::BoardView;
-use crate::{config, confirm, gamemode_btn, hspacer};
-use cmdjewel_core::board::BoardConfig;
+use crate::{audio, config, confirm, constants, gamemode_btn, hspacer, theme};
+use cmdjewel_core::board::{BoardConfig, Gamemode};
+use cmdjewel_core::levels;
 use cursive::event::Callback;
 use cursive::event::{Event, EventResult};
-use cursive::style::PaletteColor;
-use cursive::theme::Color;
 use cursive::view::{Margins, Nameable, Resizable};
 use cursive::views::{
-    Dialog, DummyView, EditView, FocusTracker, LayerPosition, LinearLayout, NamedView, OnEventView,
-    PaddedView, Panel, ProgressBar, SliderView, TextView,
+    BoxedView, Dialog, DummyView, FocusTracker, LayerPosition, LinearLayout, NamedView,
+    OnEventView, PaddedView, Panel, ProgressBar, TextView,
 };
 use cursive::{Cursive, View, XY};
 
+pub mod commands;
+pub mod hand_view;
+mod key_capture;
 mod macros;
 mod multiline_button;
+pub mod settings;
 pub mod tutorial;
 
 /// Shows the main menu, where gamemodes can be selected.
@@ -29,7 +35,7 @@ pub fn show_menu_main(s: &mut Cursive) {
             save_path = Some(p.as_os_str().to_str().unwrap().to_string())
         }
         s.call_on_name("board", move |b: &mut BoardView| {
-            config::save_board(&b.board, !b.board.is_valid())
+            config::save_board(&b.board, b.board.is_game_over())
         })
         .unwrap_or_default();
     }
@@ -40,15 +46,30 @@ pub fn show_menu_main(s: &mut Cursive) {
     let button_zen = gamemode_btn!(strings::ZEN, strings::ZEN_DESC, |s| {
         show_game(s, BoardConfig::new_zen());
     });
+    let button_poker = gamemode_btn!(strings::POKER, strings::POKER_DESC, |s| {
+        show_game(s, BoardConfig::new_poker());
+    });
+    let button_ice_storm = gamemode_btn!(strings::ICE_STORM, strings::ICE_STORM_DESC, |s| {
+        show_game(s, BoardConfig::new_ice_storm());
+    });
     let buttons = PaddedView::lrtb(
         5,
         0,
         0,
         0,
-        LinearLayout::horizontal()
-            .child(button_classic)
-            .child(hspacer!(2))
-            .child(button_zen),
+        LinearLayout::vertical()
+            .child(
+                LinearLayout::horizontal()
+                    .child(button_classic)
+                    .child(hspacer!(2))
+                    .child(button_zen),
+            )
+            .child(
+                LinearLayout::horizontal()
+                    .child(button_poker)
+                    .child(hspacer!(2))
+                    .child(button_ice_storm),
+            ),
     );
     // Adds buttons in the main menu, and a descriptor of game modes (when hovered)
     switch_screen(
@@ -59,7 +80,7 @@ pub fn show_menu_main(s: &mut Cursive) {
                 Dialog::around(buttons)
                     .title(strings::MAIN_MENU.to_lowercase())
                     .button(strings::HELP, |s| tutorial::show_help(s))
-                    .button(strings::SETTINGS, |s| show_settings(s))
+                    .button(strings::SETTINGS, |s| settings::show_settings(s))
                     .button(strings::QUIT, |s| s.quit())
                     .padding(Margins::lrtb(0, 0, 1, 0)),
             )
@@ -74,7 +95,7 @@ pub fn show_menu_main(s: &mut Cursive) {
                 .min_height(3),
             ))
             .max_width(40),
-        0x02,
+        Soundtrack::Menu,
     );
     // Show info dialog if the game is being saved for the first time
     if let Some(path) = save_path {
@@ -89,17 +110,38 @@ pub fn show_menu_splash(s: &mut Cursive) {
         s,
         LinearLayout::vertical()
             .child(TextView::new(
-                strings::LOGO_GEMS.to_string() + strings::CMDJEWEL_LOGO,
+                constants::gems::logo_gems() + strings::CMDJEWEL_LOGO,
             ))
             .child(Button::new_raw(strings::PLAY, show_menu_main)),
-        0,
+        Soundtrack::Title,
     );
 }
 
 /// This starts the game given a BoardConfig (which decides game factors such as if it is in classic/zen mode)
-pub fn show_game(s: &mut Cursive, config: BoardConfig) {
+pub fn show_game(s: &mut Cursive, mut config: BoardConfig) {
+    // Zen only has goals if they're turned on in the settings
+    if matches!(config.gamemode, Gamemode::ZEN) && config::load_config().settings.zen_goals {
+        config.goals = Some(levels::zen_goals);
+    }
     let name = config.name.clone();
-    let soundtrack = if config.infinite { 0x4b } else { 0x0d };
+    let soundtrack = match config.gamemode {
+        Gamemode::CLASSIC => Soundtrack::Classic,
+        Gamemode::ZEN => Soundtrack::Zen,
+        Gamemode::POKER => Soundtrack::Poker,
+        Gamemode::ICESTORM => Soundtrack::IceStorm,
+    };
+    // Poker hands are shown under the score
+    let hand = if config.poker {
+        BoxedView::boxed(HandView::default().with_name("hand"))
+    } else {
+        BoxedView::boxed(DummyView::new())
+    };
+    // Gamemodes with goals show them instead of a progress bar
+    let progress = if config.goals.is_some() {
+        BoxedView::boxed(TextView::new("").with_name("goals"))
+    } else {
+        BoxedView::boxed(ProgressBar::new().with_name("progress"))
+    };
     // Creates the layout for the dialog
     let layout = LinearLayout::vertical()
         .child(
@@ -118,12 +160,26 @@ pub fn show_game(s: &mut Cursive, config: BoardConfig) {
                             strings::SCORE.to_lowercase(),
                             TextView::new("█"),
                         ))
+                        .child(hand)
                         .child(TextView::new("\n")) // TODO: this is the worst way to do a margin wtf
                         .child(Button::new(strings::HINT, |s| {
                             s.call_on_name("board", |view: &mut BoardView| view.hint());
                             // Highlights the game window
                             s.focus_name("board").expect("could not focus");
                         }))
+                        .child(NamedView::new(
+                            "shuffle",
+                            Button::new(strings::SHUFFLE, |s| {
+                                s.call_on_name("board", |view: &mut BoardView| {
+                                    view.shuffle();
+                                });
+                                s.focus_name("board").expect("could not focus");
+                            }),
+                        ))
+                        .child(Button::new(strings::PAUSE, |s| {
+                            s.call_on_name("board", |view: &mut BoardView| view.paused = true);
+                            show_pause(s);
+                        }))
                         .child(
                             LinearLayout::vertical()
                                 .child(Button::new(strings::QUIT, show_menu_main)),
@@ -131,13 +187,7 @@ pub fn show_game(s: &mut Cursive, config: BoardConfig) {
                 ))
                 .child(Panel::new(NamedView::new("board", BoardView::new(config)))),
         )
-        .child(PaddedView::lrtb(
-            1,
-            1,
-            0,
-            0,
-            ProgressBar::new().with_name("progress"),
-        ));
+        .child(PaddedView::lrtb(1, 1, 0, 0, progress));
 
     // Creates the dialog
     let game_dialog = Dialog::around(layout).title(name);
@@ -146,122 +196,63 @@ pub fn show_game(s: &mut Cursive, config: BoardConfig) {
     switch_screen(s, game_dialog, soundtrack);
 }
 
-/// Shows the settings dialog.
-pub fn show_settings(s: &mut Cursive) {
-    let settings = config::load_config().settings;
-    let mut slider = SliderView::horizontal(25); // TODO 25 is a constant
-    slider.set_value((settings.music_vol * (slider.get_max_value() - 1) as f32) as usize);
-    slider.set_on_change(|_, v| {
-        let mut cfg = config::load_config();
-        cfg.settings.music_vol = v as f32 / 24.; // TODO 24 is a constant; 25 - 1
-        config::save_config(&cfg);
-        it2play_rs::set_global_volume((cfg.settings.music_vol * 128.) as u16);
-    });
-    s.add_layer(
+/// Shows the pause menu over a game. The board is hidden (and stops updating) until the game is resumed.
+pub fn show_pause(s: &mut Cursive) {
+    let mut menu = OnEventView::new(
         Dialog::around(
-            LinearLayout::vertical().child(
-                LinearLayout::horizontal()
-                    .child(TextView::new(strings::MUSIC_VOL))
-                    .child(hspacer!(2))
-                    .child(slider),
-            ),
+            LinearLayout::vertical()
+                .child(Button::new(strings::RESUME, resume))
+                .child(Button::new(strings::RESTART, |s| {
+                    confirm!(s, strings::WARN_RESTART, |s| {
+                        let config = s
+                            .call_on_name("board", |b: &mut BoardView| {
+                                // Throw away the save so the new game starts from scratch
+                                config::save_board(&b.board, true);
+                                b.board.config_ref().clone()
+                            })
+                            .unwrap();
+                        s.pop_layer();
+                        s.pop_layer();
+                        show_game(s, config);
+                    })
+                }))
+                .child(Button::new(strings::SETTINGS, settings::show_settings))
+                .child(Button::new(strings::HELP, tutorial::show_help))
+                .child(Button::new(strings::SAVE_QUIT, |s| {
+                    s.pop_layer();
+                    show_menu_main(s);
+                })),
         )
-        .title(strings::SETTINGS)
-        .button(strings::RESET, |s| {
-            confirm!(s, strings::WARN_RESET, |s| {
-                config::reset_config();
-                s.pop_layer().unwrap();
-                s.pop_layer().unwrap();
-            })
-        })
-        .button(strings::BACK, |s| {
-            s.pop_layer().unwrap();
-        })
-        .padding(Margins::lrtb(1, 1, 1, 0)),
+        .title(strings::PAUSED),
     );
+    // Whatever paused the game can also resume it
+    config::load_config()
+        .settings
+        .keys
+        .events()
+        .into_iter()
+        .filter(|&(_, action)| action == Action::Pause)
+        .for_each(|(event, _)| menu.set_on_event(event, resume));
+    s.add_layer(menu);
+}
+
+/// Closes the pause menu and resumes the game.
+fn resume(s: &mut Cursive) {
+    s.pop_layer();
+    s.call_on_name("board", |b: &mut BoardView| b.paused = false);
 }
 
 /// Initialises setting commands by creating a callback for the colon key
 pub fn init_commands(s: &mut Cursive) {
-    s.add_global_callback(':', |s| {
-        let mut edit_view = EditView::new().on_submit(|s: &mut Cursive, command: &str| {
-            // Be generous and trim/lowercase commands
-            let command = command.trim().to_lowercase();
-            s.pop_layer();
-            // Animation debugging
-            if command == "explode" {
-                s.call_on_name("board", |view: &mut BoardView| view.animation_explode());
-            } else if command == "warp" {
-                s.call_on_name("board", |view: &mut BoardView| view.animation_warp());
-            }
-            // Other debugging
-            else if command == "autoplay" {
-                s.call_on_name("board", |view: &mut BoardView| {
-                    view.autoplay = !view.autoplay;
-                });
-            } else if command == "noanims" {
-                s.call_on_name("board", |view: &mut BoardView| {
-                    view.animations_enabled = !view.animations_enabled;
-                });
-            }
-            // Going to the main menu
-            else if command == "main" || command == "m" {
-                show_menu_main(s);
-            }
-            // Starting new games
-            else if command == "play"
-                || command == "play classic"
-                || command == "p"
-                || command == "p classic"
-            {
-                show_game(s, BoardConfig::new_classic());
-            } else if command == "play zen" || command == "p zen" {
-                show_game(s, BoardConfig::new_zen());
-            }
-            // Vim keys
-            else if command == "q" || command == "qa" {
-                // Save and quit
-                // If a game exists, save it
-                s.call_on_name("board", |b: &mut BoardView| {
-                    config::save_board(&b.board, false)
-                })
-                .unwrap_or_default();
-                s.quit();
-            } else if command == "q!" || command == "qa!" {
-                // Force quit
-                s.quit();
-            } else if command == "h" || command == "hint" {
-                s.call_on_name("board", |view: &mut BoardView| view.hint());
-            } else {
-                // In case nothing was recognized, display a help window.
-                s.add_layer(Dialog::info(strings::CMD_NOT_FOUND));
-            }
-        });
-        edit_view.set_filler(" ");
-        s.add_layer(
-            Dialog::new()
-                .title(strings::COMMAND)
-                .content(
-                    OnEventView::new(
-                        LinearLayout::horizontal()
-                            .child(TextView::new("> "))
-                            .child(edit_view.full_width()),
-                    )
-                    .on_event(Event::Key(cursive::event::Key::Esc), |s| {
-                        s.pop_layer();
-                    }),
-                )
-                .with_name("command")
-                .fixed_width(32),
-        );
-    });
+    s.add_global_callback(':', commands::show_console);
 }
 
 /// Switches the topmost layer with a new layer, `view`. Since screens are displayed on their own layers (e.g. splash screen, main menu screen, games), this effectively fulfills the role of switching screens.
 /// We'll use the name "_screen" to denote one of these screens. I'm hesitant to use "scene" as terminology here since we're working with an immediate mode GUI instead of a scene structure like Godot or Unity.
-fn switch_screen<T: View>(s: &mut Cursive, view: T, soundtrack: u16) {
+fn switch_screen<T: View>(s: &mut Cursive, view: T, soundtrack: Soundtrack) {
     // Switch module order for the screen
-    let vol = (config::load_config().settings.music_vol * 128.) as u16;
+    let settings = config::load_config().settings;
+    let vol = settings.music_vol;
     let mut vol_m = vol;
     // Play an animation! If applicable.
     if let Some(layer_position) = s.screen_mut().find_layer_from_name("_screen") {
@@ -270,6 +261,8 @@ fn switch_screen<T: View>(s: &mut Cursive, view: T, soundtrack: u16) {
         let max_ticks = 10;
         let slide = pos.y > max_ticks;
         let palette = s.current_theme().palette.clone();
+        // Fade from the theme's own colors, since the palette might only have 256 or 16 colors to work with
+        let source = theme::current();
         s.set_user_data(view);
         s.screen_mut()
             .add_transparent_layer(DummyView::new().with_name("_overlay"));
@@ -279,20 +272,15 @@ fn switch_screen<T: View>(s: &mut Cursive, view: T, soundtrack: u16) {
                 pos.y -= 1;
             }
             s.reposition_layer(layer_position, XY::absolute(pos));
-            // Fade out colors.
-            s.update_theme(|t| {
-                if let Color::Rgb(br, bg, bb) = PaletteColor::Background.resolve(&t.palette) {
-                    PaletteColor::all().for_each(|p| {
-                        if let Color::Rgb(r, g, b) = p.resolve(&t.palette) {
-                            t.palette[p] =
-                                Color::Rgb(r / 2 + br / 2, g / 2 + bg / 2, b / 2 + bb / 2);
-                        }
-                    });
-                }
-            });
+            // Fade out colors, halving the distance to the background each tick.
+            s.set_theme(
+                source
+                    .faded(1. - 0.5f32.powi(ticks as i32 + 1))
+                    .to_cursive(),
+            );
             // Fade out volume.
-            vol_m -= vol / max_ticks as u16;
-            it2play_rs::set_global_volume(vol_m);
+            vol_m = (vol_m - vol / max_ticks as f32).max(0.);
+            audio::set_volume(vol_m);
             // Increase ticks
             ticks += 1;
             // Swap layers, remove callback
@@ -310,12 +298,13 @@ fn switch_screen<T: View>(s: &mut Cursive, view: T, soundtrack: u16) {
                     t.palette = palette.clone();
                 });
                 // Play music
-                it2play_rs::play(soundtrack);
-                it2play_rs::set_global_volume(vol);
+                audio::play_soundtrack(&settings.music, soundtrack);
+                audio::set_volume(vol);
             }
         });
     } else {
         // Otherwise just add the view
         s.add_layer(view.with_name("_screen"));
+        audio::play_soundtrack(&settings.music, soundtrack);
     }
 }
@@ -0,0 +1,332 @@
+use crate::animations::AnimationSpeed;
+use crate::audio::{self, Soundtrack};
+use crate::config::keys::{Action, Keybindings, Preset};
+use crate::constants::strings;
+use crate::theme::{
+    cells::CellSize, colorblind::ColorblindMode, depth::ColorDepth, glyphs::GlyphSet,
+};
+use crate::ui::key_capture::KeyCapture;
+use crate::ui::multiline_button::Button;
+use crate::view::BoardView;
+use crate::{config, confirm, hspacer, theme, vspacer};
+use cursive::view::{Margins, Nameable, Resizable};
+use cursive::views::{Checkbox, Dialog, EditView, LinearLayout, SelectView, SliderView, TextView};
+use cursive::Cursive;
+
+/// How many steps volume sliders have
+const VOLUME_STEPS: usize = 25;
+
+/// Width of labels next to settings, so everything lines up
+const LABEL_WIDTH: usize = 13;
+
+/// Shows the settings dialog.
+pub fn show_settings(s: &mut Cursive) {
+    let settings = config::load_config().settings;
+    let music_slider = volume_slider(settings.music_vol, |cfg, v| {
+        cfg.settings.music_vol = v;
+        audio::set_volume(v);
+    });
+    let sfx_slider = volume_slider(settings.sfx_vol, |cfg, v| {
+        cfg.settings.sfx_vol = v;
+        audio::set_sfx_volume(v);
+        audio::play_sfx(audio::Sfx::Swap);
+    });
+    let themes = theme::themes();
+    let theme_select = SelectView::new()
+        .popup()
+        .with_all(themes.iter().map(|t| (t.name.clone(), t.name.clone())))
+        .selected(
+            themes
+                .iter()
+                .position(|t| t.name == settings.theme)
+                .unwrap_or(0),
+        )
+        .on_submit(|s, name: &String| {
+            let mut cfg = config::load_config();
+            cfg.settings.theme = name.clone();
+            config::save_config(&cfg);
+            theme::apply(s, theme::find(name));
+        });
+    let colorblind_select =
+        option_select(&ColorblindMode::ALL, settings.colorblind, |_, cfg, mode| {
+            cfg.settings.colorblind = mode;
+            theme::set_colorblind(mode);
+        });
+    let glyphs_select = option_select(&GlyphSet::ALL, settings.glyphs, |_, cfg, glyphs| {
+        cfg.settings.glyphs = glyphs;
+        theme::set_glyphs(glyphs);
+    });
+    let depth_select = option_select(&ColorDepth::ALL, settings.color_depth, |s, cfg, depth| {
+        cfg.settings.color_depth = depth;
+        theme::set_color_depth(depth);
+        // Colors outside of the board only change when the theme is applied again
+        theme::apply(s, theme::find(&cfg.settings.theme));
+    });
+    let speed_select = option_select(
+        &AnimationSpeed::ALL,
+        settings.animation_speed,
+        |_, cfg, speed| cfg.settings.animation_speed = speed,
+    );
+    let cell_size_select = option_select(&CellSize::ALL, settings.cell_size, |_, cfg, size| {
+        cfg.settings.cell_size = size
+    });
+    let sticky_swap = Checkbox::new()
+        .with_checked(settings.sticky_swap)
+        .on_change(|s, checked| {
+            let mut cfg = config::load_config();
+            cfg.settings.sticky_swap = checked;
+            config::save_config(&cfg);
+            s.call_on_name("board", |b: &mut BoardView| b.reload_settings());
+        });
+    // Boards are only made at the start of a game, so there's nothing to reload
+    let fair_boards = Checkbox::new()
+        .with_checked(settings.no_free_matches)
+        .on_change(|_, checked| {
+            let mut cfg = config::load_config();
+            cfg.settings.no_free_matches = checked;
+            config::save_config(&cfg);
+        });
+    let zen_goals = Checkbox::new()
+        .with_checked(settings.zen_goals)
+        .on_change(|_, checked| {
+            let mut cfg = config::load_config();
+            cfg.settings.zen_goals = checked;
+            config::save_config(&cfg);
+        });
+    let mut layout = LinearLayout::vertical()
+        .child(labelled(strings::MUSIC_VOL, music_slider))
+        .child(labelled(strings::SFX_VOL, sfx_slider))
+        .child(labelled(strings::THEME, theme_select))
+        .child(labelled(strings::COLORBLIND, colorblind_select))
+        .child(labelled(strings::GLYPHS, glyphs_select))
+        .child(labelled(strings::CELL_SIZE, cell_size_select))
+        .child(labelled(strings::COLORS, depth_select))
+        .child(labelled(strings::ANIMATION_SPEED, speed_select))
+        .child(labelled(strings::STICKY_SWAP, sticky_swap))
+        .child(labelled(strings::FAIR_BOARDS, fair_boards))
+        .child(labelled(strings::ZEN_GOALS, zen_goals));
+    // Let people know why they can't hear anything
+    if !audio::is_available() {
+        layout.add_child(TextView::new(strings::AUDIO_UNAVAILABLE));
+    } else if !audio::sfx_available() {
+        layout.add_child(TextView::new(strings::SFX_UNAVAILABLE));
+    }
+    layout.add_child(vspacer!());
+    layout.add_child(
+        LinearLayout::horizontal()
+            .child(Button::new(strings::MUSIC, show_music))
+            .child(hspacer!())
+            .child(Button::new(strings::KEYS, show_keys)),
+    );
+    s.add_layer(
+        Dialog::around(layout)
+            .title(strings::SETTINGS)
+            .button(strings::RESET, |s| {
+                confirm!(s, strings::WARN_RESET, |s| {
+                    config::reset_config();
+                    s.pop_layer().unwrap();
+                    s.pop_layer().unwrap();
+                })
+            })
+            .button(strings::BACK, |s| {
+                s.pop_layer().unwrap();
+            })
+            .padding(Margins::lrtb(1, 1, 1, 0)),
+    );
+}
+
+/// Shows the music settings, where songs can be picked for each screen and a custom module can be loaded.
+pub fn show_music(s: &mut Cursive) {
+    let music = config::load_config().settings.music;
+    let tracks = audio::tracks();
+    let mut layout = LinearLayout::vertical();
+    // Pick a song for each soundtrack
+    [
+        (strings::TITLE_SCREEN, Soundtrack::Title),
+        (strings::MAIN_MENU, Soundtrack::Menu),
+        (strings::CLASSIC, Soundtrack::Classic),
+        (strings::ZEN, Soundtrack::Zen),
+        (strings::POKER, Soundtrack::Poker),
+        (strings::ICE_STORM, Soundtrack::IceStorm),
+    ]
+    .into_iter()
+    .for_each(|(label, soundtrack)| {
+        let selected = tracks
+            .iter()
+            .position(|&(_, order)| order == music.get(soundtrack))
+            .unwrap_or(0);
+        let select = SelectView::new()
+            .popup()
+            .with_all(tracks.clone())
+            .selected(selected)
+            .on_submit(move |_, &order: &u16| {
+                let mut cfg = config::load_config();
+                cfg.settings.music.set(soundtrack, order);
+                config::save_config(&cfg);
+                // Preview the song
+                audio::play(order);
+            });
+        layout.add_child(labelled(label, select));
+    });
+    // Load a custom module
+    layout.add_child(vspacer!());
+    layout.add_child(TextView::new(strings::CUSTOM_MODULE));
+    layout.add_child(
+        EditView::new()
+            .content(music.custom_module.clone().unwrap_or_default())
+            .on_submit(load_custom_module)
+            .with_name("custom_module")
+            .full_width(),
+    );
+    s.add_layer(
+        Dialog::around(layout)
+            .title(strings::MUSIC)
+            .button(strings::LOAD, |s| {
+                let path = s
+                    .call_on_name("custom_module", |v: &mut EditView| v.get_content())
+                    .unwrap();
+                load_custom_module(s, &path);
+            })
+            .button(strings::USE_BUNDLED, |s| {
+                let mut cfg = config::load_config();
+                cfg.settings.music = Default::default();
+                config::save_config(&cfg);
+                audio::load(None);
+                audio::play_soundtrack(&cfg.settings.music, Soundtrack::Menu);
+                s.pop_layer();
+                show_music(s);
+            })
+            .button(strings::BACK, |s| {
+                s.pop_layer();
+            })
+            .padding(Margins::lrtb(1, 1, 1, 0))
+            .max_width(48),
+    );
+}
+
+/// Shows the keybinding settings, where keys can be bound to each action.
+pub fn show_keys(s: &mut Cursive) {
+    let keys = config::load_config().settings.keys;
+    let mut presets = LinearLayout::horizontal();
+    Preset::ALL.into_iter().for_each(|preset| {
+        presets.add_child(Button::new(preset.to_string(), move |s| {
+            update_keys(s, |keys| *keys = Keybindings::preset(preset))
+        }));
+    });
+    let mut layout = LinearLayout::vertical().child(labelled(strings::PRESET, presets));
+    layout.add_child(vspacer!());
+    Action::ALL.into_iter().for_each(|action| {
+        let bound = keys.get(action);
+        let label = if bound.is_empty() {
+            strings::NO_KEYS.to_string()
+        } else {
+            bound.join(", ")
+        };
+        layout.add_child(labelled(
+            &action.to_string(),
+            Button::new_raw(label, move |s| {
+                s.add_layer(
+                    Dialog::around(
+                        LinearLayout::vertical()
+                            .child(TextView::new(strings::key_prompt(&action.to_string())))
+                            .child(KeyCapture::new(move |s, key| {
+                                s.pop_layer();
+                                update_keys(s, move |keys| match key {
+                                    Some(key) => keys.bind(action, key),
+                                    None => keys.get_mut(action).clear(),
+                                });
+                            })),
+                    )
+                    .title(strings::KEYS)
+                    .max_width(40),
+                );
+            }),
+        ));
+    });
+    s.add_layer(
+        Dialog::around(layout)
+            .title(strings::KEYS)
+            .button(strings::BACK, |s| {
+                s.pop_layer();
+            })
+            .padding(Margins::lrtb(1, 1, 1, 0)),
+    );
+}
+
+/// Changes keybindings, saves them, and reopens the keybinding settings to show the changes.
+/// Games that are already running pick up the new keys too.
+fn update_keys<F: FnOnce(&mut Keybindings)>(s: &mut Cursive, change: F) {
+    let mut cfg = config::load_config();
+    change(&mut cfg.settings.keys);
+    config::save_config(&cfg);
+    s.call_on_name("board", |b: &mut BoardView| b.reload_settings());
+    s.pop_layer();
+    show_keys(s);
+}
+
+/// Replaces the soundtrack with a module from disk and starts playing it.
+/// Every screen starts off playing the first song in the module.
+fn load_custom_module(s: &mut Cursive, path: &str) {
+    match audio::read_module(path.trim()) {
+        Ok(bytes) => {
+            let mut cfg = config::load_config();
+            cfg.settings.music = config::data::Music {
+                title: 0,
+                menu: 0,
+                classic: 0,
+                zen: 0,
+                poker: 0,
+                ice_storm: 0,
+                custom_module: Some(path.trim().to_string()),
+            };
+            config::save_config(&cfg);
+            audio::load(Some(bytes));
+            audio::play(0);
+            s.pop_layer();
+            show_music(s);
+        }
+        Err(e) => s.add_layer(Dialog::info(strings::module_error(&e))),
+    }
+}
+
+/// Creates a slider for a volume between 0 and 1. When it changes, `on_change` is called with the config and the new volume, and then the config is saved.
+fn volume_slider<F>(volume: f32, on_change: F) -> SliderView
+where
+    F: Fn(&mut config::data::Config, f32) + 'static + Send + Sync,
+{
+    let mut slider = SliderView::horizontal(VOLUME_STEPS);
+    slider.set_value((volume * (VOLUME_STEPS - 1) as f32) as usize);
+    slider.set_on_change(move |_, v| {
+        let mut cfg = config::load_config();
+        on_change(&mut cfg, v as f32 / (VOLUME_STEPS - 1) as f32);
+        config::save_config(&cfg);
+    });
+    slider
+}
+
+/// Creates a popup for picking one of `options`. When one is picked, `on_submit` is called with the config and the
+/// new option, and then the config is saved (and picked up by any game that's running).
+fn option_select<T, F>(options: &[T], selected: T, on_submit: F) -> SelectView<T>
+where
+    T: Copy + PartialEq + std::fmt::Display + Send + Sync + 'static,
+    F: Fn(&mut Cursive, &mut config::data::Config, T) + 'static + Send + Sync,
+{
+    SelectView::new()
+        .popup()
+        .with_all(options.iter().map(|&option| (option.to_string(), option)))
+        .selected(options.iter().position(|&o| o == selected).unwrap_or(0))
+        .on_submit(move |s, &option: &T| {
+            let mut cfg = config::load_config();
+            on_submit(s, &mut cfg, option);
+            config::save_config(&cfg);
+            s.call_on_name("board", |b: &mut BoardView| b.reload_settings());
+        })
+}
+
+/// Puts a label to the left of a setting.
+fn labelled<V: cursive::View>(label: &str, view: V) -> LinearLayout {
+    LinearLayout::horizontal()
+        .child(TextView::new(format!("{: <1$}", label, LABEL_WIDTH)))
+        .child(hspacer!(2))
+        .child(view)
+}
@@ -141,9 +141,7 @@ fn board(b: Vec<[Gem; 3]>) -> PaddedView<ResizedView<Panel<LinearLayout>>> {
     b.iter().for_each(|row| {
         let mut h = LinearLayout::horizontal();
         row.iter().for_each(|gem| {
-            h.add_child(
-                TextView::new(format!(" {} ", gems::gem_string(*gem))).style(gems::gem_color(*gem)),
-            )
+            h.add_child(TextView::new(gems::gem_cell(*gem)).style(gems::gem_color(*gem)))
         });
         layout.add_child(h);
     });
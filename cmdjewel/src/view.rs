@@ -1,18 +1,34 @@
-use crate::animations::{AnimationDetails, AnimationType, AnimationView};
+use crate::animations::effects::{self, EFFECT_LENGTH};
+use crate::animations::{
+    AnimationDetails, AnimationSpeed, AnimationType, AnimationView, HINT_REGION_SIZE, POPUP_LENGTH,
+};
+use crate::audio::{self, Sfx};
+use crate::config::keys::Action;
 use crate::constants::strings;
-use crate::{config, constants, ui};
+use crate::theme::cells::{self, CellSize};
+use crate::ui::hand_view::HandView;
+use crate::{config, constants, theme, ui};
 use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::events::BoardEvent;
 use cmdjewel_core::gems::Gem;
+use cmdjewel_core::levels::LevelStats;
 use cmdjewel_core::matches::Match;
 use cmdjewel_core::point;
 use cmdjewel_core::point::Point;
+use cmdjewel_core::poker::PokerHand;
 use cursive::direction::Direction;
 use cursive::event::{Event, EventResult, MouseEvent};
-use cursive::theme::{Color, ColorStyle, PaletteColor};
+use cursive::theme::{ColorStyle, Effect, PaletteColor};
 use cursive::traits::Resizable;
 use cursive::view::CannotFocus;
-use cursive::views::{Dialog, ProgressBar, TextView};
+use cursive::views::{Button, Dialog, ProgressBar, TextView};
 use cursive::{Printer, Vec2};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest gap between refreshes that counts towards how long a level took. Refreshes stop while the game's paused, so
+/// the first one after that would count the whole pause otherwise.
+const MAX_REFRESH_GAP: Duration = Duration::from_millis(250);
 
 /// Cursor modes
 pub enum CursorMode {
@@ -37,10 +53,41 @@ pub struct BoardView {
     pub cursor_mode: CursorMode,
     pub autoplay: bool,
     pub animations_enabled: bool,
+    /// Events for each key that's bound to an action
+    keys: Vec<(Event, Action)>,
+    /// The board as it was before the last swap
+    undo: Option<Board>,
+    /// When paused, the board is hidden and stops updating
+    pub paused: bool,
+    /// If the cursor follows gems after they're swapped
+    sticky_swap: bool,
+    /// The move the last hint was for, until a move is made
+    hint: Option<(Point<usize>, point::Direction)>,
+    /// When the last hint was given, for hint cooldowns
+    last_hint: Option<Instant>,
+    /// How fast gems swap and fall
+    animation_speed: AnimationSpeed,
+    /// Where the top left of the board was last drawn on screen, so fullscreen animations can line up with it
+    screen_offset: Mutex<Vec2>,
+    /// How big cells should be drawn
+    cell_size: CellSize,
+    /// How big cells are being drawn, worked out from `cell_size` and the space there is
+    scale: usize,
+    /// If the board ran out of moves and is waiting for the player to shuffle it
+    stuck: bool,
+    /// If the player should be asked to shuffle (on the next refresh)
+    ask_shuffle: bool,
+    /// When the board was last refreshed, for timing levels
+    last_refresh: Instant,
+    /// A level that was just finished, to show a summary of (on the next refresh)
+    summary: Option<LevelStats>,
+    /// The last poker hand that was scored, and how many points it was worth
+    last_hand: Option<(PokerHand, u32)>,
 }
 
 impl BoardView {
     pub fn new(config: BoardConfig) -> Self {
+        let settings = config::load_config().settings;
         BoardView {
             board: config::new_board(config),
             has_focus: false,
@@ -49,18 +96,194 @@ impl BoardView {
             autoplay: false,
             animations_enabled: true,
             cursor_down: Point(0, 0),
+            keys: settings.keys.events(),
+            undo: None,
+            paused: false,
+            sticky_swap: settings.sticky_swap,
+            hint: None,
+            last_hint: None,
+            animation_speed: settings.animation_speed,
+            screen_offset: Mutex::new(Vec2::zero()),
+            cell_size: settings.cell_size,
+            scale: 1,
+            stuck: false,
+            ask_shuffle: false,
+            last_refresh: Instant::now(),
+            summary: None,
+            last_hand: None,
+        }
+    }
+
+    /// Changes the board by hand (for debug commands), forgetting anything that depended on how it was before.
+    pub fn edit(&mut self, f: impl FnOnce(&mut Board)) {
+        f(&mut self.board);
+        self.undo = None;
+        self.clear_hint();
+    }
+
+    /// Gets how many characters across and down each cell is drawn.
+    pub fn cell(&self) -> Vec2 {
+        cells::cell(self.scale)
+    }
+
+    /// Gets where the top left of the board was last drawn on screen.
+    pub fn screen_offset(&self) -> Vec2 {
+        *self.screen_offset.lock().unwrap()
+    }
+
+    /// Reloads settings that affect controls, so changes made in settings apply to games that have already started.
+    pub fn reload_settings(&mut self) {
+        let settings = config::load_config().settings;
+        self.keys = settings.keys.events();
+        self.sticky_swap = settings.sticky_swap;
+        self.animation_speed = settings.animation_speed;
+        self.cell_size = settings.cell_size;
+    }
+
+    /// Returns true once everything has settled down: the board is full, nothing is matching, and nothing is moving.
+    fn is_settled(&self) -> bool {
+        self.animations
+            .iter()
+            .all(|a| a.animation_type.is_overlay())
+            && self.board.is_full()
+            && self.board.get_matches().is_empty()
+    }
+
+    /// Puts the board back to how it was before the last swap. This only works once everything has settled down.
+    pub fn undo(&mut self) {
+        if self.is_settled() {
+            if let Some(board) = self.undo.take() {
+                self.clear_hint();
+                self.board = board;
+                self.cursor_mode = CursorMode::Normal;
+            }
         }
     }
 
-    /// Sets the cursor to the first swappable gem
+    /// Gives a hint for the best move. The first time, a region with the move is highlighted. Asking again shows the
+    /// exact gem and which way to swap it.
     pub fn hint(&mut self) {
-        for i in 0..self.board.as_ref().len() {
-            let point = self.board.index_to_point(i);
-            if self.board.is_valid_gem(point) {
-                self.board.set_cursor(point);
-                break;
+        if !self.is_settled() {
+            return;
+        }
+        if let Some((point, direction)) = self.hint {
+            self.clear_hint();
+            self.hint = Some((point, direction));
+            self.board.set_cursor(point);
+            self.animations.push(AnimationDetails {
+                point,
+                duration: 90,
+                animation_type: AnimationType::Hint(direction),
+                wait: 0,
+            });
+            return;
+        }
+        // Some gamemodes make you wait between hints
+        let cooldown = Duration::from_secs(self.board.config_ref().hint_cooldown as u64);
+        if self.last_hint.is_some_and(|t| t.elapsed() < cooldown) {
+            return;
+        }
+        if let Some((point, direction)) = self.board.take_hint() {
+            self.hint = Some((point, direction));
+            self.last_hint = Some(Instant::now());
+            let corner = |x: usize| (x / HINT_REGION_SIZE) * HINT_REGION_SIZE;
+            self.animations.push(AnimationDetails {
+                point: Point(corner(point.0), corner(point.1)),
+                duration: 60,
+                animation_type: AnimationType::HintRegion,
+                wait: 0,
+            });
+        }
+    }
+
+    /// Shuffles the board with one of the game's shuffles, sliding every gem over from where it was. This only works
+    /// once everything has settled down. Returns true if the board was shuffled.
+    pub fn shuffle(&mut self) -> bool {
+        if !self.is_settled() {
+            return false;
+        }
+        let Some(from) = self.board.use_shuffle() else {
+            return false;
+        };
+        self.clear_hint();
+        self.undo = None;
+        self.stuck = false;
+        if self.animations_enabled {
+            let length = self.animation_speed.frames() * 4;
+            for (i, &from) in from.iter().enumerate() {
+                self.animations.push(AnimationDetails {
+                    point: self.board.index_to_point(i),
+                    duration: length,
+                    animation_type: AnimationType::Slide {
+                        from: self.board.index_to_point(from),
+                        length,
+                    },
+                    wait: 0,
+                });
             }
         }
+        true
+    }
+
+    /// Deals with the board running out of moves. Depending on the gamemode, it gets shuffled, the player gets asked
+    /// to shuffle it, or the game ends.
+    fn check_moves(&mut self) {
+        if self.stuck {
+            return;
+        }
+        // Ice reaching the top ends the game, moves or not. Wait for the last move to finish, since it might break
+        // the ice back down.
+        if self.board.is_frozen() {
+            if self.is_settled() {
+                self.stuck = true;
+                self.end_game();
+            }
+            return;
+        }
+        if self.board.is_valid() || !self.board.is_full() {
+            return;
+        }
+        if self.board.is_game_over() {
+            self.end_game();
+        } else if !self.board.config_ref().auto_shuffle {
+            self.stuck = true;
+            self.ask_shuffle = true;
+        } else if self.is_settled() && !self.shuffle() {
+            // Some boards can't be shuffled into a move, so those get new gems instead
+            self.regenerate();
+        }
+    }
+
+    /// Drops a whole new board in, in place of a shuffle.
+    fn regenerate(&mut self) {
+        self.board.regenerate();
+        self.clear_hint();
+        self.undo = None;
+        self.stuck = false;
+    }
+
+    /// Ends the game, even if there are shuffles left.
+    pub fn end_game(&mut self) {
+        self.board.set_shuffles_left(0);
+        self.animation_explode();
+    }
+
+    /// Floats some text up from a point on the board.
+    fn popup(&mut self, point: Point<usize>, text: String, callout: bool) {
+        if self.animations_enabled {
+            self.animations.push(AnimationDetails {
+                point,
+                duration: POPUP_LENGTH,
+                animation_type: AnimationType::Popup { text, callout },
+                wait: 0,
+            });
+        }
+    }
+
+    /// Forgets the current hint and stops showing it.
+    fn clear_hint(&mut self) {
+        self.hint = None;
+        self.animations.retain(|a| !a.animation_type.is_hint());
     }
 
     /// Adds an AnimationDetails for an explosion.
@@ -86,13 +309,167 @@ impl BoardView {
     }
 
     /// Swap two gems at the cursor in a given direction--but only if a valid move is possible.
+    /// With sticky swaps, the cursor follows the gem that was moved.
     fn attempt_swap(&mut self, direction: point::Direction) {
-        if self.board.is_valid_move(self.board.get_cursor(), direction) {
-            self.board.swap(direction);
+        self.clear_hint();
+        let before = self.board.clone();
+        let cursor = self.board.get_cursor();
+        let neighbor = self.neighbor(direction);
+        let swapped = self.board.try_swap(direction);
+        if let Some(neighbor) = neighbor.filter(|_| self.animations_enabled) {
+            let length = self.animation_speed.frames() * 2;
+            // Hypercubes don't actually move when they're swapped, so they bounce like an invalid swap
+            let moved = swapped && self.board.get_gem(cursor) == before.get_gem(neighbor);
+            [(cursor, neighbor), (neighbor, cursor)]
+                .into_iter()
+                .for_each(|(point, other)| {
+                    self.animations.push(AnimationDetails {
+                        point,
+                        duration: length,
+                        animation_type: if moved {
+                            AnimationType::Slide {
+                                from: other,
+                                length,
+                            }
+                        } else {
+                            AnimationType::Bounce {
+                                towards: other,
+                                length,
+                            }
+                        },
+                        wait: 0,
+                    })
+                });
+        }
+        if swapped {
+            self.undo = Some(before);
+            if self.sticky_swap {
+                self.board.move_cursor(direction);
+            }
         }
         self.cursor_mode = CursorMode::Normal;
     }
 
+    /// Gets the point next to the cursor in a direction, if it's on the board.
+    fn neighbor(&self, direction: point::Direction) -> Option<Point<usize>> {
+        let cursor = self.board.get_cursor();
+        let at_edge = match direction {
+            point::Direction::Left => cursor.0 == 0,
+            point::Direction::Up => cursor.1 == 0,
+            _ => false,
+        };
+        // Points can't go below 0, so don't even work out where the neighbor would be
+        if at_edge {
+            None
+        } else {
+            Some(self.board.get_destination(&direction)).filter(|&p| self.board.is_in_board(p))
+        }
+    }
+
+    /// Does whatever an action bound to a key is supposed to do.
+    fn on_action(&mut self, action: Action) -> EventResult {
+        match action {
+            Action::MoveLeft => self.move_cursor(point::Direction::Left),
+            Action::MoveRight => self.move_cursor(point::Direction::Right),
+            Action::MoveUp => self.move_cursor(point::Direction::Up),
+            Action::MoveDown => self.move_cursor(point::Direction::Down),
+            Action::ToggleSwap => {
+                if let CursorMode::Normal = self.cursor_mode {
+                    self.cursor_mode = CursorMode::Swap
+                } else {
+                    self.cursor_mode = CursorMode::Normal
+                }
+                EventResult::consumed()
+            }
+            Action::SwapLeft => self.swap(point::Direction::Left),
+            Action::SwapRight => self.swap(point::Direction::Right),
+            Action::SwapUp => self.swap(point::Direction::Up),
+            Action::SwapDown => self.swap(point::Direction::Down),
+            Action::Hint => {
+                self.hint();
+                EventResult::consumed()
+            }
+            Action::Undo => {
+                self.undo();
+                EventResult::consumed()
+            }
+            Action::Shuffle => {
+                self.shuffle();
+                EventResult::consumed()
+            }
+            Action::Pause => {
+                self.paused = true;
+                EventResult::with_cb(ui::show_pause)
+            }
+            Action::Command => EventResult::with_cb(ui::commands::show_console),
+        }
+    }
+
+    /// Swaps the gem at the cursor in a direction, without having to go into swap mode first.
+    fn swap(&mut self, direction: point::Direction) -> EventResult {
+        self.attempt_swap(direction);
+        EventResult::consumed()
+    }
+
+    /// Reacts to everything that's happened on the board since the last time this was called, with sounds and popups.
+    fn handle_events(&mut self) {
+        self.board
+            .take_events()
+            .into_iter()
+            .for_each(|event| match event {
+                BoardEvent::Swap => audio::play_sfx(Sfx::Swap),
+                BoardEvent::InvalidSwap => audio::play_sfx(Sfx::InvalidSwap),
+                BoardEvent::Cascade(step) => {
+                    audio::play_sfx(Sfx::Cascade(step));
+                    // Long chains get called out in the middle of the board
+                    if step >= 2 {
+                        let callout =
+                            strings::CALLOUTS[(step as usize - 2).min(strings::CALLOUTS.len() - 1)];
+                        let middle = self.board.get_width() / 2;
+                        self.popup(Point(middle, middle), callout.to_string(), true);
+                    }
+                }
+                BoardEvent::Matched {
+                    at,
+                    points,
+                    cascade,
+                    ..
+                } => self.popup(at, strings::popup_points(points, cascade), false),
+                BoardEvent::PokerHand(hand, points) => {
+                    audio::play_sfx(Sfx::PokerHand);
+                    self.last_hand = Some((hand, points));
+                    let middle = self.board.get_width() / 2;
+                    self.popup(
+                        Point(middle, middle),
+                        strings::poker_hand(hand).to_string(),
+                        true,
+                    );
+                }
+                BoardEvent::IceBroken { column, points, .. } => {
+                    audio::play_sfx(Sfx::IceBreak);
+                    let bottom = self.board.get_width() - 1;
+                    self.popup(
+                        Point(column, bottom),
+                        strings::popup_points(points, 0),
+                        false,
+                    );
+                }
+                BoardEvent::Detonation(Gem::Flame(_)) => audio::play_sfx(Sfx::Flame),
+                BoardEvent::Detonation(Gem::Star(_) | Gem::Supernova(_)) => {
+                    audio::play_sfx(Sfx::Star)
+                }
+                BoardEvent::Detonation(Gem::Hypercube(_)) => audio::play_sfx(Sfx::Hypercube),
+                BoardEvent::Detonation(_) => (),
+                BoardEvent::LevelComplete(stats) => self.summary = Some(stats),
+                BoardEvent::LevelUp(_) => audio::play_sfx(Sfx::LevelUp),
+                BoardEvent::Shuffle => {
+                    audio::play_sfx(Sfx::Shuffle);
+                    let middle = self.board.get_width() / 2;
+                    self.popup(Point(middle, middle), strings::SHUFFLED.to_string(), true);
+                }
+            });
+    }
+
     /// Updates all animations. Animations are automatically destroyed in the event loop.
     fn update_animations(&mut self) {
         // Reduce duration of each animation
@@ -153,6 +530,32 @@ impl BoardView {
                             animation_type: AnimationType::Blink(true),
                             wait: r * 8,
                         });
+                        // ...and set them off
+                        let effect = match v.board.get_gem(p) {
+                            Gem::Flame(_) => Some(AnimationType::Burst),
+                            Gem::Star(_) | Gem::Supernova(_) => Some(AnimationType::Beam),
+                            Gem::Hypercube(_) => {
+                                let index = v.board.point_to_index(p);
+                                let mut targets = v.board.activate_special_gem(index, false);
+                                if targets.is_empty() {
+                                    // Hypercubes set off by other gems pick up a color from the match instead
+                                    m.children
+                                        .iter()
+                                        .for_each(|c| targets.extend_from_slice(&c.gems));
+                                }
+                                targets.retain(|&t| t != p);
+                                Some(AnimationType::Lightning(targets))
+                            }
+                            _ => None,
+                        };
+                        if let Some(effect) = effect {
+                            v.animations.push(AnimationDetails {
+                                point: p,
+                                duration: EFFECT_LENGTH,
+                                animation_type: effect,
+                                wait: r * 8,
+                            });
+                        }
                     }
                 });
                 m.children.iter().for_each(|c| {
@@ -165,10 +568,8 @@ impl BoardView {
                 .iter()
                 .for_each(|m| recurse(0, m, self, &mut points));
         }
-        // Explode if not valid
-        if !self.board.is_valid() && self.board.is_full() {
-            self.animation_explode();
-        }
+        // Shuffle (or explode) if there are no moves
+        self.check_moves();
     }
 
     /// Updates board logic.
@@ -180,14 +581,28 @@ impl BoardView {
                 self.board.fill_gem_buffer();
             }
         }
+        let before = self.board.as_ref().to_vec();
         self.board.slide_down();
+        // Animate every gem that fell into a new cell
+        if self.animations_enabled {
+            let length = self.animation_speed.frames();
+            for (i, &gem) in self.board.as_ref().iter().enumerate() {
+                if gem != Gem::Empty && gem != before[i] {
+                    self.animations.push(AnimationDetails {
+                        point: self.board.index_to_point(i),
+                        duration: length,
+                        animation_type: AnimationType::Fall { length },
+                        wait: 0,
+                    });
+                }
+            }
+        }
         self.board.update_level();
         if self.autoplay && self.board.is_full() {
-            self.hint();
-            self.attempt_swap(point::Direction::Left);
-            self.attempt_swap(point::Direction::Right);
-            self.attempt_swap(point::Direction::Up);
-            self.attempt_swap(point::Direction::Down);
+            if let Some((point, direction)) = self.board.best_move() {
+                self.board.set_cursor(point);
+                self.attempt_swap(direction);
+            }
         }
     }
 
@@ -222,18 +637,34 @@ impl BoardView {
 
 impl cursive::view::View for BoardView {
     fn draw(&self, printer: &Printer) {
+        *self.screen_offset.lock().unwrap() = printer.offset;
         let is_valid = !self
             .animations
             .iter()
             .any(|x| x.animation_type == AnimationType::Explosion);
-        if !is_valid {
+        // Hide the board while paused, so nobody can plan moves without the clock running
+        if !is_valid || self.paused {
             return;
         }
+        let moving: Vec<&AnimationDetails> = self
+            .animations
+            .iter()
+            .filter(|anim| anim.animation_type.is_motion())
+            .collect();
+        let cell = self.cell();
         // Loop through each gem/cell
         for i in 0..self.board.as_ref().len() {
-            let string = constants::gems::gem_string(self.board.as_ref()[i]);
+            let mut rows = constants::gems::gem_cell_rows(self.board.as_ref()[i], self.scale);
             let point = self.board.index_to_point(i);
+            // Gems that are moving get drawn afterwards, on top of everything else
+            if moving.iter().any(|anim| anim.point == point) {
+                continue;
+            }
             let mut color = constants::gems::gem_color(self.board.as_ref()[i]);
+            // Ice covers the bottom of its column
+            if self.board.get_ice().covers(point) {
+                color = ColorStyle::new(color.front, theme::current().muted.color());
+            }
             // Swap colors for highlighted gems.
             self.animations.iter().for_each(|anim| {
                 if anim.point.0 == point.0
@@ -245,16 +676,48 @@ impl cursive::view::View for BoardView {
                     color = color.invert();
                 }
             });
-            // If there's no animation happening, you can theme the cell under whatever conditions.
-            if self.animations.is_empty() {
+            // Hints
+            self.animations
+                .iter()
+                .filter(|anim| anim.wait == 0)
+                .for_each(|anim| match anim.animation_type {
+                    AnimationType::HintRegion
+                        if (anim.point.0..anim.point.0 + HINT_REGION_SIZE).contains(&point.0)
+                            && (anim.point.1..anim.point.1 + HINT_REGION_SIZE)
+                                .contains(&point.1) =>
+                    {
+                        color = ColorStyle::new(
+                            color.front,
+                            theme::current().highlight_inactive.color(),
+                        );
+                    }
+                    AnimationType::Hint(direction) if anim.point == point => {
+                        // Blink, with an arrow where the gem needs to go
+                        if anim.duration % 10 < 5 {
+                            color = color.invert();
+                        }
+                        let row = &mut rows[self.scale / 2];
+                        row.pop();
+                        row.push(theme::glyphs().arrow(direction));
+                    }
+                    _ => (),
+                });
+            // If there's no animation happening (other than hints and popups), you can theme the cell under whatever
+            // conditions.
+            if self
+                .animations
+                .iter()
+                .all(|a| a.animation_type.is_overlay())
+            {
                 // for instance, if the cursor is in the same position, set some custom colors.
                 if i == self.board.point_to_index(self.board.get_cursor()) {
+                    let theme = theme::current();
                     color = match self.cursor_mode {
                         CursorMode::Normal => {
-                            ColorStyle::new(Color::Rgb(46, 52, 64), Color::Rgb(213, 219, 230))
+                            ColorStyle::new(theme.background.color(), theme.primary.color())
                         }
                         CursorMode::Swap => {
-                            ColorStyle::new(Color::Rgb(213, 219, 230), Color::Rgb(190, 96, 105))
+                            ColorStyle::new(theme.primary.color(), theme.highlight.color())
                         }
                     }
                 }
@@ -265,13 +728,91 @@ impl cursive::view::View for BoardView {
             }
             // Print things, with spacing!
             printer.with_color(color, |printer| {
-                printer.print((point.0 * 3, point.1), &format!(" {} ", string))
+                rows.iter().enumerate().for_each(|(y, row)| {
+                    printer.print((point.0 * cell.x, point.1 * cell.y + y), row)
+                })
             });
         }
+        // Draw moving gems wherever they are, rounded to the nearest character
+        moving.iter().for_each(|anim| {
+            let gem = self.board.get_gem(anim.point);
+            let position = anim.position().unwrap();
+            let (x, y) = (
+                (position.0 * cell.x as f32).round(),
+                (position.1 * cell.y as f32).round(),
+            );
+            if gem == Gem::Empty || x < 0. || y < 0. {
+                return;
+            }
+            let color = if self.has_focus {
+                constants::gems::gem_color(gem)
+            } else {
+                ColorStyle::new(PaletteColor::Secondary, PaletteColor::Tertiary)
+            };
+            printer.with_color(color, |printer| {
+                constants::gems::gem_cell_rows(gem, self.scale)
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, row)| printer.print((x as usize, y as usize + i), row))
+            });
+        });
+        // Special gems going off
+        let size = self.board.get_width();
+        self.animations
+            .iter()
+            .filter(|anim| anim.wait == 0)
+            .for_each(|anim| {
+                let progress = 1.0 - anim.duration as f32 / EFFECT_LENGTH as f32;
+                let color = constants::gems::gem_color(self.board.get_gem(anim.point));
+                match &anim.animation_type {
+                    AnimationType::Burst => {
+                        effects::burst(printer, anim.point, progress, size, cell, color)
+                    }
+                    AnimationType::Beam => {
+                        effects::beam(printer, anim.point, progress, size, cell, color)
+                    }
+                    AnimationType::Lightning(targets) => {
+                        effects::lightning(printer, anim.point, targets, progress, cell, color)
+                    }
+                    _ => (),
+                }
+            });
+        // Popups, floating up a cell every few frames
+        let theme = theme::current();
+        self.animations.iter().for_each(|anim| {
+            if let AnimationType::Popup { text, callout } = &anim.animation_type {
+                let rise = (POPUP_LENGTH - anim.duration) as usize * cell.y / 8;
+                let width = text.chars().count();
+                // Centered over the cell, but kept on the board
+                let x = (anim.point.0 * cell.x + cell.x / 2)
+                    .saturating_sub(width / 2)
+                    .min(printer.size.x.saturating_sub(width));
+                let y = (anim.point.1 * cell.y + cell.y / 2).saturating_sub(rise);
+                let (color, effect) = if *callout {
+                    (
+                        ColorStyle::new(theme.view.color(), theme.title.color()),
+                        Effect::Bold,
+                    )
+                } else {
+                    (
+                        ColorStyle::new(theme.title.color(), theme.view.color()),
+                        Effect::Simple,
+                    )
+                };
+                printer.with_color(color, |printer| {
+                    printer.with_effect(effect, |printer| printer.print((x, y), text))
+                });
+            }
+        });
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        let width = self.board.get_width();
+        cells::cell(self.cell_size.fit(width, constraint)) * width
     }
 
-    fn required_size(&mut self, _: Vec2) -> Vec2 {
-        (self.board.get_width() * 3, self.board.get_width()).into()
+    fn layout(&mut self, size: Vec2) {
+        self.scale = self.cell_size.fit(self.board.get_width(), size);
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
@@ -282,6 +823,14 @@ impl cursive::view::View for BoardView {
         } else {
             self.has_focus = true;
         }
+        // Nothing happens while paused
+        if self.paused {
+            return EventResult::Ignored;
+        }
+        // Keys bound to actions come first
+        if let Some(&(_, action)) = self.keys.iter().find(|(e, _)| *e == event) {
+            return self.on_action(action);
+        }
         // Handle events
         match event {
             Event::Mouse {
@@ -289,15 +838,16 @@ impl cursive::view::View for BoardView {
                 position,
                 event,
             } => {
-                // NOTE: 3 is some constant that represents the width of each gem
+                let cell = self.cell();
                 let point: Point<i32> = Point(
-                    (position.x as i32 - offset.x as i32) / 3,
-                    position.y as i32 - offset.y as i32,
+                    (position.x as i32 - offset.x as i32).div_euclid(cell.x as i32),
+                    (position.y as i32 - offset.y as i32).div_euclid(cell.y as i32),
                 );
                 match event {
                     MouseEvent::Press(_) => {
-                        if point.0 < 8 && point.1 < 8 && point.0 >= 0 && point.1 >= 0 {
-                            self.cursor_down = Point(point.0 as usize, point.1 as usize);
+                        let pressed = Point(point.0 as usize, point.1 as usize);
+                        if point.0 >= 0 && point.1 >= 0 && self.board.is_in_board(pressed) {
+                            self.cursor_down = pressed;
                             self.board.set_cursor(self.cursor_down);
                             self.cursor_mode = CursorMode::Swap;
                             EventResult::Consumed(None)
@@ -343,6 +893,10 @@ impl cursive::view::View for BoardView {
                 }
             }
             Event::Refresh => {
+                let now = Instant::now();
+                self.board
+                    .add_time((now - self.last_refresh).min(MAX_REFRESH_GAP));
+                self.last_refresh = now;
                 let mut initial_level = self.board.get_level() + 1;
                 let is_valid = !self
                     .animations
@@ -353,11 +907,15 @@ impl cursive::view::View for BoardView {
                 let mut exists_running_animation = false;
                 let mut is_animation_removed = false;
                 for i in (0..self.animations.len()).rev() {
+                    let animation_type = &self.animations[i].animation_type;
                     if self.animations[i].duration == 0 {
-                        // Remove finished animations
+                        // Remove finished animations. Gems that are done moving still need to be checked for matches,
+                        // and overlays have nothing to do with them, so those don't count.
+                        if !animation_type.is_motion() && !animation_type.is_overlay() {
+                            is_animation_removed = true;
+                        }
                         self.animations.remove(i);
-                        is_animation_removed = true;
-                    } else {
+                    } else if !animation_type.is_overlay() {
                         exists_running_animation = true;
                     }
                 }
@@ -366,14 +924,33 @@ impl cursive::view::View for BoardView {
                         self.create_animations();
                     }
                     // Update board
-                    if self.animations.is_empty() {
+                    if self
+                        .animations
+                        .iter()
+                        .all(|a| a.animation_type.is_overlay())
+                    {
                         self.update_board();
                     }
                 }
+                self.handle_events();
                 // Updates GUI (yes i have to make all these variables i love rust multithreading)
                 let score = self.board.get_score();
                 let level = self.board.get_level() + 1;
                 let progress = self.board.get_level_progress() * 100.;
+                let shuffles = self.board.get_shuffles_left();
+                let shuffle_penalty = self.board.config_ref().shuffle_penalty;
+                let ask_shuffle = std::mem::take(&mut self.ask_shuffle);
+                let summary = self.summary.take();
+                let hand = self.board.get_hand().cards().to_vec();
+                let last_hand = self.last_hand;
+                let stats = self.board.get_level_stats();
+                let goals = self
+                    .board
+                    .get_goals()
+                    .into_iter()
+                    .map(|goal| strings::goal(goal, stats))
+                    .collect::<Vec<_>>()
+                    .join("\n");
                 // Hacks initial_level if there is a warp animation
                 if self
                     .animations
@@ -396,17 +973,47 @@ impl cursive::view::View for BoardView {
                     s.call_on_name("progress", |p: &mut ProgressBar| {
                         p.set_value(progress as usize)
                     });
+                    s.call_on_name("goals", |t: &mut TextView| t.set_content(goals.clone()));
+                    s.call_on_name("hand", |h: &mut HandView| {
+                        h.set_cards(hand.clone());
+                        h.set_last(last_hand);
+                    });
+                    s.call_on_name("shuffle", |b: &mut Button| {
+                        b.set_label(strings::shuffle_button(shuffles))
+                    });
+                    // Out of moves, but there are shuffles left
+                    if ask_shuffle {
+                        s.add_layer(
+                            Dialog::text(strings::no_moves(shuffles, shuffle_penalty))
+                                .button(strings::SHUFFLE, |s| {
+                                    s.pop_layer();
+                                    s.call_on_name("board", |b: &mut BoardView| {
+                                        if !b.shuffle() {
+                                            b.end_game();
+                                        }
+                                    });
+                                })
+                                .button(strings::END_GAME, |s| {
+                                    s.pop_layer();
+                                    s.call_on_name("board", |b: &mut BoardView| b.end_game());
+                                }),
+                        );
+                    }
                     // Explodes if applicable
                     if !is_valid {
-                        let data = s
+                        audio::play_sfx(Sfx::GameOver);
+                        let (data, offset, cell) = s
                             .call_on_name("board", |b: &mut BoardView| {
-                                b.board.as_ref().to_vec() // Return board as vec
+                                (b.board.as_ref().to_vec(), b.screen_offset(), b.cell())
+                                // Return board as vec
                             })
                             .unwrap();
                         s.screen_mut().add_fullscreen_layer(
                             AnimationView::new(
                                 crate::animations::explosion::Explosion::new(data.len(), 1.0),
                                 data,
+                                offset,
+                                cell,
                             )
                             .with_on_finish(move |s| {
                                 s.add_layer(
@@ -417,51 +1024,38 @@ impl cursive::view::View for BoardView {
                             .full_screen(),
                         );
                     }
+                    // Sums up the level that was just finished. This goes under the warp, so it shows up once the warp
+                    // is over, and the board waits until it's closed.
+                    if let Some(stats) = summary {
+                        s.call_on_name("board", |b: &mut BoardView| b.paused = true);
+                        s.add_layer(
+                            Dialog::text(strings::level_summary(&stats))
+                                .title(strings::level_complete(level - 1))
+                                .button(strings::CONTINUE, |s| {
+                                    s.pop_layer();
+                                    s.call_on_name("board", |b: &mut BoardView| b.paused = false);
+                                }),
+                        );
+                    }
                     // Warps if available
                     if initial_level != level {
-                        let data = s
-                            .call_on_name("board", |b: &mut BoardView| b.board.as_ref().to_vec())
+                        let (data, offset, cell) = s
+                            .call_on_name("board", |b: &mut BoardView| {
+                                (b.board.as_ref().to_vec(), b.screen_offset(), b.cell())
+                            })
                             .unwrap();
                         s.screen_mut().add_fullscreen_layer(
                             AnimationView::new(
                                 crate::animations::warp::Warp::new(data.len(), 1.0),
                                 data,
+                                offset,
+                                cell,
                             )
                             .full_screen(),
                         )
                     }
                 })
             }
-            Event::Char(c) => match c.to_ascii_lowercase() {
-                ' ' => {
-                    if let CursorMode::Normal = self.cursor_mode {
-                        self.cursor_mode = CursorMode::Swap
-                    } else {
-                        self.cursor_mode = CursorMode::Normal
-                    }
-                    EventResult::consumed()
-                }
-                ':' => EventResult::Ignored,
-                'h' => self.move_cursor(point::Direction::Left),
-                'l' => self.move_cursor(point::Direction::Right),
-                'k' => self.move_cursor(point::Direction::Up),
-                'j' => self.move_cursor(point::Direction::Down),
-                _ => EventResult::with_cb(move |s| {
-                    s.add_layer(Dialog::info(strings::KEY_NOT_FOUND));
-                }),
-            },
-            Event::Key(cursive::event::Key::Left) => self.move_cursor(point::Direction::Left),
-            Event::Key(cursive::event::Key::Right) => self.move_cursor(point::Direction::Right),
-            Event::Key(cursive::event::Key::Up) => self.move_cursor(point::Direction::Up),
-            Event::Key(cursive::event::Key::Down) => self.move_cursor(point::Direction::Down),
-            Event::Key(cursive::event::Key::Enter) => {
-                if let CursorMode::Normal = self.cursor_mode {
-                    self.cursor_mode = CursorMode::Swap
-                } else {
-                    self.cursor_mode = CursorMode::Normal
-                }
-                EventResult::consumed()
-            }
             _ => EventResult::Ignored,
         }
     }
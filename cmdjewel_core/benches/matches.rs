@@ -0,0 +1,124 @@
+// Benchmarks for the checks that run after every move: is there a move left, and which moves are there. Each one is
+// timed the slow way (looking at every gem) and the bitboard way, on the same boards. Zen refills run the move check
+// over and over until they find a board with a move, so they're timed too.
+
+use std::hint::black_box;
+
+use cmdjewel_core::bitboard::Bitboard;
+use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::gems::Gem;
+use cmdjewel_core::point::{Direction, Point};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+/// Makes a few full boards with nothing left to match, the same ones every run.
+fn boards() -> Vec<Board> {
+    (0..16)
+        .map(|seed| {
+            let mut board = Board::new(BoardConfig::new_zen());
+            board.reseed(seed);
+            while !board.is_full() || !board.get_matches().is_empty() {
+                if board.is_buffer_empty() {
+                    if board.is_full() {
+                        board.update_matching_gems();
+                    } else {
+                        board.fill_gem_buffer();
+                    }
+                }
+                board.slide_down();
+            }
+            board
+        })
+        .collect()
+}
+
+fn points() -> impl Iterator<Item = Point<usize>> {
+    (0..64).map(|i| Point(i % 8, i / 8))
+}
+
+fn is_valid(c: &mut Criterion) {
+    let boards = boards();
+    let mut group = c.benchmark_group("is_valid");
+    group.bench_function("per gem", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(points().any(|p| board.is_valid_gem(p)));
+            }
+        })
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(board.is_valid());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn valid_moves(c: &mut Criterion) {
+    let boards = boards();
+    let mut group = c.benchmark_group("valid_moves");
+    group.bench_function("per gem", |b| {
+        b.iter(|| {
+            for board in &boards {
+                let moves: Vec<_> = points()
+                    .flat_map(|p| [Direction::Right, Direction::Down].map(|d| (p, d)))
+                    .filter(|&(p, d)| board.is_valid_move(p, d))
+                    .collect();
+                black_box(moves);
+            }
+        })
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(board.valid_moves());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn has_matches(c: &mut Criterion) {
+    let boards = boards();
+    let mut group = c.benchmark_group("has_matches");
+    group.bench_function("get_matches", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(!board.get_matches().is_empty());
+            }
+        })
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            for board in &boards {
+                black_box(Bitboard::new(board.as_ref()).has_matches());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn zen_refill(c: &mut Criterion) {
+    let boards: Vec<Board> = boards()
+        .into_iter()
+        .map(|board| {
+            // Knock out the middle of the board, like a big cascade would
+            let mut data: [Gem; 64] = board.as_ref().try_into().unwrap();
+            data[16..48].fill(Gem::Empty);
+            Board::new_controlled(BoardConfig::new_zen(), data, 0, 0, 0.0)
+        })
+        .collect();
+    c.bench_function("zen_refill", |b| {
+        b.iter(|| {
+            for board in &boards {
+                let mut board = board.clone();
+                board.fill_gem_buffer();
+                black_box(board);
+            }
+        })
+    });
+}
+
+criterion_group!(benches, is_valid, valid_moves, has_matches, zen_refill);
+criterion_main!(benches);
@@ -0,0 +1,161 @@
+// Boards as bitmasks, one per color, for finding matches and moves without looking at every gem one at a time.
+//
+// Each mask has a bit for every cell on the board, at `y * BOARD_SIZE + x`. Shifting a mask moves every gem in it at once, so
+// "is there a line of 3 anywhere" is a couple of shifts and ANDs per color instead of 64 lookups, and "is there a move
+// anywhere" is a few more.
+
+use crate::constants::BOARD_SIZE;
+use crate::gems::{Gem, GemColor};
+use crate::point::Direction;
+
+// Every cell on the board needs its own bit
+const _: () = assert!(BOARD_SIZE * BOARD_SIZE == u64::BITS as usize);
+
+/// Every cell in the leftmost column
+const LEFT_COLUMN: u64 = 0x0101_0101_0101_0101;
+
+/// Cells to keep after shifting by -2, -1, 0, 1, or 2 columns, so gems don't wrap around onto the next row.
+const KEEP_COLUMNS: [u64; 5] = [
+    !(LEFT_COLUMN | LEFT_COLUMN << 1),
+    !LEFT_COLUMN,
+    !0,
+    !(LEFT_COLUMN << (BOARD_SIZE - 1)),
+    !(LEFT_COLUMN << (BOARD_SIZE - 2) | LEFT_COLUMN << (BOARD_SIZE - 1)),
+];
+
+/// Pairs of offsets that make a line of 3 with a cell, going across and then down.
+const LINES: [[(i32, i32); 2]; 6] = [
+    [(-2, 0), (-1, 0)],
+    [(-1, 0), (1, 0)],
+    [(1, 0), (2, 0)],
+    [(0, -2), (0, -1)],
+    [(0, -1), (0, 1)],
+    [(0, 1), (0, 2)],
+];
+
+/// Gets a mask where each cell is set if the cell `dx` across and `dy` down from it is set in `mask`. Cells that would
+/// be looking off the board are never set.
+fn offset(mask: u64, dx: i32, dy: i32) -> u64 {
+    let shift = dy * BOARD_SIZE as i32 + dx;
+    let shifted = if shift >= 0 {
+        mask >> shift
+    } else {
+        mask << -shift
+    };
+    shifted & KEEP_COLUMNS[(dx + 2) as usize]
+}
+
+/// Gets a mask of every cell that would make a line with gems in `mask`, if it was the same color. Lines through
+/// `partner` (the cell it's being swapped with) don't count, since that gem won't be there anymore.
+fn line_ends(mask: u64, partner: (i32, i32)) -> u64 {
+    LINES
+        .iter()
+        .filter(|pair| !pair.contains(&partner))
+        .fold(0, |ends, [a, b]| {
+            ends | (offset(mask, a.0, a.1) & offset(mask, b.0, b.1))
+        })
+}
+
+/// A board as a bitmask for each color, plus one for hypercubes (which don't have a color)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Bitboard {
+    colors: [u64; 7],
+    hypercubes: u64,
+}
+
+impl Bitboard {
+    /// Makes bitmasks out of 64 gems.
+    pub fn new(data: &[Gem]) -> Self {
+        let mut board = Bitboard::default();
+        for (i, &gem) in data.iter().enumerate().take(BOARD_SIZE * BOARD_SIZE) {
+            if let Some(mask) = board.mask_mut(gem) {
+                *mask |= 1 << i;
+            }
+        }
+        board
+    }
+
+    /// Gets the mask for a color.
+    pub fn color(&self, color: GemColor) -> u64 {
+        self.colors[color as usize]
+    }
+
+    /// Puts a gem in a cell, replacing whatever was there.
+    pub fn set(&mut self, i: usize, gem: Gem) {
+        for mask in self.colors.iter_mut().chain([&mut self.hypercubes]) {
+            *mask &= !(1 << i);
+        }
+        if let Some(mask) = self.mask_mut(gem) {
+            *mask |= 1 << i;
+        }
+    }
+
+    /// Gets the mask a gem goes in, if it goes in one.
+    fn mask_mut(&mut self, gem: Gem) -> Option<&mut u64> {
+        match gem {
+            Gem::Normal(color) | Gem::Flame(color) | Gem::Star(color) | Gem::Supernova(color) => {
+                Some(&mut self.colors[color as usize])
+            }
+            Gem::Hypercube(_) => Some(&mut self.hypercubes),
+            Gem::Empty => None,
+        }
+    }
+
+    /// Gets the mask of every gem that's in a line of 3 or more.
+    pub fn lines(&self) -> u64 {
+        self.colors.iter().fold(0, |lines, &mask| {
+            // Find where lines start, then fill in the two gems after
+            let across = mask & offset(mask, 1, 0) & offset(mask, 2, 0);
+            let down = mask & offset(mask, 0, 1) & offset(mask, 0, 2);
+            lines
+                | across
+                | offset(across, -1, 0)
+                | offset(across, -2, 0)
+                | down
+                | offset(down, 0, -1)
+                | offset(down, 0, -2)
+        })
+    }
+
+    /// Gets the mask of every cell where a gem of a color would finish a line of 3 or more.
+    pub fn line_ends(&self, color: GemColor) -> u64 {
+        // Nothing's being swapped, so no lines get left out
+        line_ends(self.color(color), (0, 0))
+    }
+
+    /// Returns true if any gems are lined up in a match.
+    pub fn has_matches(&self) -> bool {
+        self.lines() != 0
+    }
+
+    /// Gets the mask of every gem that makes a match when it's swapped in a direction (not counting hypercubes, which
+    /// don't need to make a match).
+    pub fn moves(&self, direction: Direction) -> u64 {
+        let (dx, dy) = match direction {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        };
+        self.colors.iter().fold(0, |moves, &mask| {
+            // The gem coming in makes a line where it lands...
+            let incoming = offset(mask, dx, dy) & line_ends(mask, (dx, dy));
+            // ...or the gem going out does, on the other side
+            let outgoing = offset(mask, -dx, -dy) & line_ends(mask, (-dx, -dy));
+            moves | incoming | offset(outgoing, dx, dy)
+        })
+    }
+
+    /// Counts the moves on the board: each swap that makes a match (once, not once for each gem in it), plus each
+    /// hypercube.
+    pub fn move_count(&self) -> u32 {
+        self.moves(Direction::Right).count_ones()
+            + self.moves(Direction::Down).count_ones()
+            + self.hypercubes.count_ones()
+    }
+
+    /// Returns true if there's a move to make, either a swap that makes a match or a hypercube to use.
+    pub fn has_move(&self) -> bool {
+        self.hypercubes != 0 || (self.moves(Direction::Right) | self.moves(Direction::Down)) != 0
+    }
+}
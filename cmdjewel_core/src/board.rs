@@ -1,9 +1,19 @@
 // Handles game logic.
 
+use std::time::Duration;
+
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
+use crate::bitboard::Bitboard;
+use crate::events::BoardEvent;
+use crate::generator;
+use crate::ice::{self, Ice};
+use crate::levels::{self, Goal, LevelStats, Special};
 use crate::matches::{self, Match};
 use crate::point::{Direction, Point};
+use crate::poker::Hand;
 
 use crate::constants::*;
 
@@ -14,12 +24,39 @@ use crate::gems::*;
 pub enum Gamemode {
     CLASSIC,
     ZEN,
+    POKER,
+    ICESTORM,
 }
 
+#[derive(Clone)]
 pub struct BoardConfig {
     pub infinite: bool,
     pub name: String,
     pub gamemode: Gamemode,
+    /// Points taken away every time a hint is used
+    pub hint_penalty: u32,
+    /// Seconds to wait between hints
+    pub hint_cooldown: u32,
+    /// How many times the board can be shuffled in a game
+    pub shuffles: u8,
+    /// Points taken away every time the board is shuffled
+    pub shuffle_penalty: u32,
+    /// If the board shuffles itself when it runs out of moves, instead of waiting for the player to
+    pub auto_shuffle: bool,
+    /// Fewest moves refills try to leave on the board, in infinite gamemodes. Fewer moves makes for a harder game.
+    pub min_moves: u8,
+    /// Most moves refills try to leave on the board, in infinite gamemodes
+    pub max_moves: u8,
+    /// If new gems (filling a new board, or refilling one) never line up on their own, so nothing scores before the
+    /// player's made a move. Infinite gamemodes always work like this.
+    pub no_free_matches: bool,
+    /// Goals for each level, if the gamemode has them. Levels with goals are over once every goal is met, instead of
+    /// once enough gems have been cleared.
+    pub goals: Option<fn(u8) -> Vec<Goal>>,
+    /// If each match deals its color into a poker hand, which scores when it's full
+    pub poker: bool,
+    /// If ice rises up the columns as moves are made, and the game's over once a column is iced to the top
+    pub ice: bool,
 }
 
 impl BoardConfig {
@@ -28,6 +65,17 @@ impl BoardConfig {
             infinite: false,
             name: "classic".into(),
             gamemode: Gamemode::CLASSIC,
+            hint_penalty: 0,
+            hint_cooldown: 0,
+            shuffles: 0,
+            shuffle_penalty: 0,
+            auto_shuffle: false,
+            min_moves: 1,
+            max_moves: u8::MAX,
+            no_free_matches: true,
+            goals: None,
+            poker: false,
+            ice: false,
         }
     }
 
@@ -36,11 +84,41 @@ impl BoardConfig {
             infinite: true,
             name: "zen".into(),
             gamemode: Gamemode::ZEN,
+            hint_penalty: 0,
+            hint_cooldown: 0,
+            shuffles: 1,
+            shuffle_penalty: 0,
+            auto_shuffle: true,
+            min_moves: ZEN_MIN_MOVES,
+            max_moves: ZEN_MAX_MOVES,
+            no_free_matches: true,
+            goals: None,
+            poker: false,
+            ice: false,
+        }
+    }
+
+    pub fn new_poker() -> Self {
+        BoardConfig {
+            name: "poker".into(),
+            gamemode: Gamemode::POKER,
+            poker: true,
+            ..BoardConfig::new_classic()
+        }
+    }
+
+    pub fn new_ice_storm() -> Self {
+        BoardConfig {
+            name: "ice storm".into(),
+            gamemode: Gamemode::ICESTORM,
+            ice: true,
+            ..BoardConfig::new_zen()
         }
     }
 }
 
 /// cmdjewel boards.
+#[derive(Clone)]
 pub struct Board {
     // All boards in Bejeweled (and hence, cmdjewel) are 8x8.
     // Whatever this is resized to, it MUST be a valid power of two. (or else we get runtime errors ☹)
@@ -53,8 +131,22 @@ pub struct Board {
     score: u32,
     level: u8,
     level_progress: f32,
+    // What's happened this level, and how many points the last move has scored so far
+    stats: LevelStats,
+    move_points: u32,
+    // Colors dealt so far (in gamemodes with poker)
+    hand: Hand,
+    // How far up each column ice has got (in gamemodes with ice)
+    ice: Ice,
+    // Shuffles left this game
+    shuffles_left: u8,
     // Config
     config: BoardConfig,
+    // Events that haven't been taken yet, and how many clears have happened since the last swap
+    events: Vec<BoardEvent>,
+    cascade: u8,
+    // Where new gems (and shuffles) come from
+    rng: StdRng,
 }
 
 impl Board {
@@ -67,7 +159,15 @@ impl Board {
             score: 0,
             level: 0,
             level_progress: 0.0,
+            stats: LevelStats::default(),
+            move_points: 0,
+            hand: Hand::default(),
+            ice: Ice::default(),
+            shuffles_left: config.shuffles,
             config,
+            events: Vec::new(),
+            cascade: 0,
+            rng: StdRng::from_entropy(),
         }
     }
 
@@ -80,27 +180,16 @@ impl Board {
     ) -> Self {
         Board {
             data,
-            buffer: [Gem::Empty; 64],
-            cursor: Point(0, 0),
-            direction: Direction::Left,
             score,
             level,
             level_progress,
-            config,
+            ..Board::new(config)
         }
     }
 
-    fn from_data(data: [Gem; 64]) -> Self {
-        Board {
-            data,
-            buffer: [Gem::Empty; 64],
-            cursor: Point(0, 0),
-            direction: Direction::Left,
-            score: 0,
-            level: 0,
-            level_progress: 0.0,
-            config: BoardConfig::new_classic(),
-        }
+    /// Returns (and clears) every event that has happened since the last call.
+    pub fn take_events(&mut self) -> Vec<BoardEvent> {
+        std::mem::take(&mut self.events)
     }
 
     /// Returns the current level as an integer.
@@ -121,24 +210,109 @@ impl Board {
             .max(PROGRESS_SWAP_MIN)
     }
 
-    /// Increments the level by 1 and resets level_progress if level_progress is geq 1
+    /// Increments the level by 1 and resets level_progress if level_progress is geq 1.
+    /// In gamemodes with goals, level_progress is how far along the goals are.
     pub fn update_level(&mut self) {
+        if let Some(goals) = self.config.goals {
+            self.level_progress = levels::goal_progress(&goals(self.level), &self.stats);
+        }
         if self.level_progress >= 1.0 {
             self.level_progress -= 1.0;
             self.level += 1;
+            // Points from the move that finished the level don't count towards the next one's best move
+            self.move_points = 0;
+            let stats = std::mem::take(&mut self.stats);
+            self.events.push(BoardEvent::LevelComplete(stats));
+            self.events.push(BoardEvent::LevelUp(self.level));
         }
     }
 
+    /// Gets the current level's goals. Gamemodes without goals don't have any.
+    pub fn get_goals(&self) -> Vec<Goal> {
+        self.config
+            .goals
+            .map_or(Vec::new(), |goals| goals(self.level))
+    }
+
+    /// Gets what's happened so far this level.
+    pub fn get_level_stats(&self) -> &LevelStats {
+        &self.stats
+    }
+
+    /// Counts time towards how long the level's been played for.
+    pub fn add_time(&mut self, time: Duration) {
+        self.stats.time += time;
+    }
+
+    /// Gets the poker hand being dealt.
+    pub fn get_hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    /// Sets the poker hand being dealt (e.g. when loading a saved game).
+    pub fn set_hand(&mut self, hand: Hand) {
+        self.hand = hand;
+    }
+
+    /// Gets how far up each column ice has got.
+    pub fn get_ice(&self) -> &Ice {
+        &self.ice
+    }
+
+    /// Sets how far up each column ice has got (e.g. when loading a saved game).
+    pub fn set_ice(&mut self, ice: Ice) {
+        self.ice = ice;
+    }
+
+    /// Sets what's happened so far this level (e.g. when loading a saved game).
+    pub fn set_level_stats(&mut self, stats: LevelStats) {
+        self.stats = stats;
+    }
+
     /// Gets a gem from a point
     pub fn get_gem(&self, point: Point<usize>) -> Gem {
         self.data[self.point_to_index(point)]
     }
 
+    /// Puts a gem at a point, replacing whatever was there.
+    pub fn set_gem(&mut self, point: Point<usize>, gem: Gem) {
+        let index = self.point_to_index(point);
+        self.data[index] = gem;
+    }
+
+    /// Replaces every gem on the board.
+    pub fn set_data(&mut self, data: [Gem; 64]) {
+        self.data = data;
+        self.buffer = [Gem::Empty; 64];
+    }
+
     /// Gets the score
     pub fn get_score(&self) -> u32 {
         self.score
     }
 
+    /// Sets the score.
+    pub fn set_score(&mut self, score: u32) {
+        self.score = score;
+    }
+
+    /// Sets the level, starting it from the beginning.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level;
+        self.level_progress = 0.0;
+        self.stats = LevelStats::default();
+    }
+
+    /// Gets how many more times the board can be shuffled this game.
+    pub fn get_shuffles_left(&self) -> u8 {
+        self.shuffles_left
+    }
+
+    /// Sets how many more times the board can be shuffled this game.
+    pub fn set_shuffles_left(&mut self, shuffles: u8) {
+        self.shuffles_left = shuffles;
+    }
+
     /// Returns true if the buffer is empty (and can be filled).
     pub fn is_buffer_empty(&self) -> bool {
         self.buffer.iter().all(|&x| x == Gem::Empty)
@@ -146,7 +320,8 @@ impl Board {
 
     /// Fills the buffer:
     ///        a. Clone data and make everything fall down.
-    ///        b. Insert new gems until everything is valid (brute force)
+    ///        b. Insert new gems (making sure there's a move, if the board is infinite, and that they don't match by
+    ///           themselves, if the config says so)
     ///        c. Record the gems we inserted and their positions in the gem buffer.
     pub fn fill_gem_buffer(&mut self) {
         // 1. Clone data and make everything fall down.
@@ -164,48 +339,25 @@ impl Board {
                 break;
             }
         }
-        // 2. If it's supposed to be infinite, but it isn't valid, do some checks...
-        // 2. Insert new gems until everything is valid (brute force)
-        let mut iterations = 0;
-        loop {
-            // Consider some case starting from data_clone
-            let case: [Gem; 64] = data_clone
-                .into_iter()
-                .map(|gem| {
-                    if gem == Gem::Empty {
-                        rand::random()
-                    } else {
-                        gem
-                    }
-                })
-                .collect::<Vec<Gem>>()
-                .try_into()
-                .unwrap_or([Gem::Empty; 64]);
-
-            // check if the case is valid
-            if iterations > 500 {
-                for i in 0..case.len() {
-                    if case[i] != data_clone[i] {
-                        self.buffer[i] = case[i];
-                    }
-                }
-                // Find the first empty spot in data_clone, make that a hypercube
-                for (i, gem) in data_clone.iter().enumerate() {
-                    if *gem == Gem::Empty {
-                        self.buffer[i] = Gem::Hypercube(GemSelector::None);
-                    }
-                }
-                break;
-            } else if !self.config.infinite || Board::from_data(case).is_valid() {
-                // Record the gems we inserted and their positions in the gem buffer.
-                for i in 0..case.len() {
-                    if case[i] != data_clone[i] {
-                        self.buffer[i] = case[i];
-                    }
+        // 2. Insert new gems. Infinite boards can't run out of moves, so they get gems picked to leave some.
+        let case = if self.config.infinite {
+            let moves = self.config.min_moves as u32..=self.config.max_moves as u32;
+            generator::fill(&data_clone, moves, &mut self.rng)
+        } else if self.config.no_free_matches {
+            generator::fill(&data_clone, 0..=u32::MAX, &mut self.rng)
+        } else {
+            data_clone.map(|gem| {
+                if gem == Gem::Empty {
+                    self.rng.r#gen()
+                } else {
+                    gem
                 }
-                break;
-            } else {
-                iterations += 1;
+            })
+        };
+        // 3. Record the gems we inserted and their positions in the gem buffer.
+        for i in 0..case.len() {
+            if case[i] != data_clone[i] {
+                self.buffer[i] = case[i];
             }
         }
     }
@@ -237,12 +389,55 @@ impl Board {
         }
     }
 
-    /// Shuffles the board (until we have a valid board).
-    pub fn shuffle(&mut self) {
-        let mut rng = rand::thread_rng();
-        while !self.is_valid() {
-            self.data.shuffle(&mut rng);
+    /// Shuffles the board until there's a move to make and nothing's already matching. Returns where each gem came
+    /// from (the gem at `i` used to be at `from[i]`), or None if no shuffle like that turned up, in which case the board
+    /// is left how it was.
+    pub fn shuffle(&mut self) -> Option<[usize; 64]> {
+        let data = self.data;
+        let mut from: [usize; 64] = std::array::from_fn(|i| i);
+        for _ in 0..SHUFFLE_ATTEMPTS {
+            from.shuffle(&mut self.rng);
+            self.data = from.map(|i| data[i]);
+            if self.is_valid() && !self.has_matches() {
+                return Some(from);
+            }
         }
+        self.data = data;
+        None
+    }
+
+    /// Shuffles the board with one of the game's shuffles, taking away points for it if the gamemode has a shuffle
+    /// penalty. Returns where each gem came from like `shuffle`, or None if there are no shuffles left (or the board
+    /// couldn't be shuffled).
+    pub fn use_shuffle(&mut self) -> Option<[usize; 64]> {
+        if self.shuffles_left == 0 || !self.is_full() {
+            return None;
+        }
+        let from = self.shuffle()?;
+        self.shuffles_left -= 1;
+        self.score = self.score.saturating_sub(self.config.shuffle_penalty);
+        self.events.push(BoardEvent::Shuffle);
+        Some(from)
+    }
+
+    /// Throws every gem away and drops in a whole new board with a move on it. This stands in for a shuffle when the
+    /// gems can't be shuffled into a move (say, when nearly all of them are the same color), so it uses one up.
+    pub fn regenerate(&mut self) {
+        let moves = if self.config.infinite {
+            self.config.min_moves as u32..=self.config.max_moves as u32
+        } else {
+            1..=u32::MAX
+        };
+        self.buffer = generator::fill(&[Gem::Empty; 64], moves, &mut self.rng);
+        self.data = [Gem::Empty; 64];
+        self.shuffles_left = self.shuffles_left.saturating_sub(1);
+        self.score = self.score.saturating_sub(self.config.shuffle_penalty);
+        self.events.push(BoardEvent::Shuffle);
+    }
+
+    /// Starts generating gems from a seed, so the same seed always makes the same gems.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
     }
 
     /// Swaps a gem with a gem in an adjacent direction, which points from the destination from the cursor. **Wrapper for private Board.swap.**
@@ -273,6 +468,26 @@ impl Board {
         }
     }
 
+    /// Swaps the gem at the cursor in a direction, but only if that makes a match.
+    /// Returns true if the gems were swapped.
+    pub fn try_swap(&mut self, direction: Direction) -> bool {
+        if self.is_valid_move(self.cursor, direction) {
+            self.swap(direction);
+            self.cascade = 0;
+            self.move_points = 0;
+            self.stats.moves += 1;
+            if self.config.ice && self.stats.moves.is_multiple_of(ICE_MOVES_PER_RISE) {
+                self.ice
+                    .rise(ice::rising_columns(self.level), &mut self.rng);
+            }
+            self.events.push(BoardEvent::Swap);
+            true
+        } else {
+            self.events.push(BoardEvent::InvalidSwap);
+            false
+        }
+    }
+
     /// Gets the width (=height) of the board.
     pub fn get_width(&self) -> usize {
         f32::sqrt(self.data.len() as f32) as usize
@@ -293,55 +508,18 @@ impl Board {
     /// Finds all gem matches recursively
     pub fn get_matches(&self) -> Vec<Match> {
         let (width, height) = (self.get_width(), self.get_width());
-        // Scan vertically and then horizontally to get matches
-        let v_matches = matches::scan_matches(&self, width, height, true);
-        let mut h_matches = matches::scan_matches(&self, width, height, false);
-        // Vertical and combined matches
-        let mut total_matches: Vec<Match> = v_matches
-            .iter()
-            .map(|m| {
-                let mut gems = m.gems.clone();
-                // Combine horizontal matches that share a gem
-                let mut shared: Option<usize> = None;
-                for i in 0..h_matches.len() {
-                    // TODO: optimize
-                    h_matches[i].gems.iter().for_each(|&gem_a| {
-                        m.gems.iter().for_each(|&gem_b| {
-                            if gem_a.0 == gem_b.0 && gem_a.1 == gem_b.1 {
-                                shared = Some(i);
-                            }
-                        })
-                    });
-                }
-                if let Some(i) = shared {
-                    gems.append(&mut h_matches[i].gems.clone());
-                    h_matches.remove(i);
-                }
-                // See what gem should be created depending on the number of gems
-                let color = self.color_at_point(&self.data, gems[0]).unwrap();
-                let what = if shared.is_some() && gems.len() == 6 {
-                    Some(Gem::Star(color))
-                } else {
-                    None
-                };
-                Match {
-                    gems: gems,
-                    at: m.at,
-                    what: what,
-                    children: vec![], // TODO: recursively check `gems` for special gems to activate, adding a Match to children
-                }
-            })
-            .collect();
-        // Horizontal matches
-        total_matches.append(&mut h_matches);
-        // TODO: Scan for activated hypercubes, too!
+        // Scan vertically and then horizontally to get every line, and put together lines that cross
+        let mut lines = matches::scan_matches(self, width, height, true);
+        lines.append(&mut matches::scan_matches(self, width, height, false));
+        let mut total_matches = matches::combine_lines(self, lines);
+        // Hypercubes that have been swapped
         for i in 0..self.data.len() {
             if let Gem::Hypercube(_) = self.data[i] {
                 let gems = self.activate_special_gem(i, false);
-                if gems.len() > 0 {
+                if !gems.is_empty() {
                     total_matches.push(Match {
-                        gems: gems,
-                        at: Point(0, 0),
+                        gems,
+                        at: self.index_to_point(i),
                         what: None,
                         children: vec![],
                     })
@@ -349,72 +527,51 @@ impl Board {
             }
         }
         // Get list of total gems
-        let mut gems: Vec<Point<usize>> = vec![];
-        total_matches.iter().for_each(|m| {
-            gems.append(&mut m.gems.clone());
-        });
-        // Common checks
+        let mut gems: Vec<Point<usize>> = total_matches
+            .iter()
+            .flat_map(|m| m.gems.iter().copied())
+            .collect();
+        let swapped = [Some(self.cursor), self.swap_destination()];
         total_matches.iter_mut().for_each(|m| {
-            // See if any gem is at the cursor
-            m.at = m.gems[0];
-            m.gems.iter().for_each(|&g| {
-                if g == self.cursor || g == self.get_destination(&self.direction) {
-                    m.at = g;
-                }
-            });
-            // See what gem should be created depending on the number of gems
-            let color = self.color_at_point(&self.data, m.gems[0]).unwrap();
-            if m.what.is_none() {
-                m.what = match m.gems.len() {
-                    5 => Some(Gem::Hypercube(GemSelector::None)),
-                    4 => Some(Gem::Flame(color)),
-                    _ => None,
-                };
+            // Special gems get made where the swap was, if it was part of the match
+            if let Some(g) = swapped.into_iter().flatten().find(|g| m.gems.contains(g)) {
+                m.at = g;
             }
             // Oh god we're doing this recursively now aren't we
+            let color = m
+                .gems
+                .iter()
+                .find_map(|&g| self.color_at_point(&self.data, g));
             self.get_matches_recursive(m, &mut gems, color);
         });
         total_matches
     }
 
-    /// Sees if a match needs child matches -- e.g. if a match has triggered a special gem
-    fn get_matches_recursive(&self, m: &mut Match, gems: &mut Vec<Point<usize>>, color: GemColor) {
+    /// Sees if a match needs child matches -- e.g. if a match has triggered a special gem. Hypercubes that get set off
+    /// take out gems of `color` (the color of whatever set them off).
+    fn get_matches_recursive(
+        &self,
+        m: &mut Match,
+        gems: &mut Vec<Point<usize>>,
+        color: Option<GemColor>,
+    ) {
         m.gems
             .iter()
             .for_each(|&g| match self.data[self.point_to_index(g)] {
                 Gem::Normal(_) => (),
                 Gem::Empty => (),
-                _ => {
-                    let mut color = color;
-                    if let Some(c) = self.color_at_point(&self.data, g) {
-                        color = c;
-                    }
-                    let mut activated = self.activate_special_gem(self.point_to_index(g), false);
-                    // Hypercube hacks!
-                    if let Gem::Hypercube(_) = self.get_gem(g) {
-                        activated =
-                            self.activate_hypercube_with_color(self.point_to_index(g), color);
-                    }
-                    // Filter out activated special gems
-                    activated = activated
-                        .iter()
-                        .map(|&p| p)
-                        .filter(|p| {
-                            let mut in_gems = false;
-                            gems.iter().for_each(|&x| {
-                                if x.0 == p.0 && x.1 == p.1 {
-                                    in_gems = if let Gem::Normal(_) = self.get_gem(x) {
-                                        false
-                                    } else {
-                                        true
-                                    }
-                                }
-                            });
-                            !in_gems
-                        })
-                        .collect();
+                gem => {
+                    let color = self.color_at_point(&self.data, g).or(color);
+                    let mut activated = match (gem, color) {
+                        (Gem::Hypercube(_), Some(color)) => {
+                            self.activate_hypercube_with_color(self.point_to_index(g), color)
+                        }
+                        _ => self.activate_special_gem(self.point_to_index(g), false),
+                    };
+                    // Filter out gems that have already been matched, so nothing gets set off twice
+                    activated.retain(|p| !gems.contains(p));
                     // Recursively call and add matched gems to `gems`/`m.children`
-                    if activated.len() > 0 {
+                    if !activated.is_empty() {
                         let mut n = Match::new(activated.clone());
                         gems.append(&mut activated);
                         self.get_matches_recursive(&mut n, gems, color);
@@ -490,13 +647,21 @@ impl Board {
                     })
                 }
             }
+            // Stars take out their row and column
             Gem::Star(_) => {
-                for i in 0..self.get_width() {
-                    if i != point.0 {
-                        to_remove.push(Point(i, point.1));
+                for i in 0..self.data.len() {
+                    let p = self.index_to_point(i);
+                    if p.0 == point.0 || p.1 == point.1 {
+                        to_remove.push(p);
                     }
-                    if i != point.1 {
-                        to_remove.push(Point(point.0, i));
+                }
+            }
+            // Supernovas take out three rows and three columns
+            Gem::Supernova(_) => {
+                for i in 0..self.data.len() {
+                    let p = self.index_to_point(i);
+                    if p.0.abs_diff(point.0) <= 1 || p.1.abs_diff(point.1) <= 1 {
+                        to_remove.push(p);
                     }
                 }
             }
@@ -512,24 +677,82 @@ impl Board {
     /// Returns a vector of the positions of all special gems that have been added.
     pub fn update_matching_gems(&mut self) {
         let matching_gems = self.get_matches();
+        let score_before = self.score;
+        // Vertical lines break the ice in their column
+        if self.config.ice {
+            let width = self.get_width();
+            for line in matches::scan_matches(self, width, width, true) {
+                let column = line.gems[0].0;
+                let rows = self.ice.break_rows(column, line.gems.len() as u8);
+                if rows > 0 {
+                    let points = rows as u32 * POINTS_ICE;
+                    self.score += points;
+                    self.events.push(BoardEvent::IceBroken {
+                        column,
+                        rows,
+                        points,
+                    });
+                }
+            }
+        }
         // Set every matching gem and (matching) special gem to empty
         fn update_recursive(b: &mut Board, m: &Match) {
             m.gems.iter().for_each(|&point| {
                 let idx = b.point_to_index(point);
+                if !matches!(b.data[idx], Gem::Empty | Gem::Normal(_)) {
+                    b.events.push(BoardEvent::Detonation(b.data[idx]));
+                }
+                if let Some(color) = Board::color_at_index(&b.data, idx) {
+                    b.stats.cleared[color as usize] += 1;
+                }
                 if b.data[idx] != Gem::Empty {
                     b.data[idx] = Gem::Empty;
                     b.score += POINTS_SWAP as u32;
-                    b.level_progress += b.get_swap_progress();
+                    // Gamemodes with goals work out their progress from the goals instead
+                    if b.config.goals.is_none() {
+                        b.level_progress += b.get_swap_progress();
+                    }
                 }
             });
+            m.children.iter().for_each(|n| {
+                update_recursive(b, n);
+            });
+            // New special gems go in last, so they don't get caught up in what made them
             if let Some(gem) = m.what {
                 b.data[b.point_to_index(m.at)] = gem;
+                if let Some(special) = Special::of(gem) {
+                    b.stats.made[special as usize] += 1;
+                }
             }
-            m.children.iter().for_each(|n| {
-                update_recursive(b, n);
+        }
+        matching_gems.iter().for_each(|m| {
+            let score = self.score;
+            // Hypercubes don't have a color, but the gems they take out do
+            let color = m
+                .gems
+                .iter()
+                .find_map(|&p| self.color_at_point(&self.data, p));
+            update_recursive(self, m);
+            self.events.push(BoardEvent::Matched {
+                at: m.at,
+                points: self.score - score,
+                cascade: self.cascade,
+                color,
             });
+            let poker = self.config.poker;
+            if let Some(hand) = color.filter(|_| poker).and_then(|c| self.hand.deal(c)) {
+                self.score += hand.points();
+                self.events.push(BoardEvent::PokerHand(hand, hand.points()));
+            }
+        });
+        if !matching_gems.is_empty() {
+            self.events.push(BoardEvent::Cascade(self.cascade));
+            self.cascade = self.cascade.saturating_add(1);
         }
-        matching_gems.iter().for_each(|m| update_recursive(self, m));
+        let points = self.score - score_before;
+        self.stats.points += points;
+        self.move_points += points;
+        self.stats.best_move = self.stats.best_move.max(self.move_points);
     }
 
     /// Returns true if the entire board is filled with gems.
@@ -539,13 +762,23 @@ impl Board {
 
     /// Returns true if you can make a move on the board.
     pub fn is_valid(&self) -> bool {
-        for i in 0..self.data.len() {
-            let point = self.index_to_point(i);
-            if self.data[i] != Gem::Empty && self.is_valid_gem(point) {
-                return true;
-            }
-        }
-        false
+        Bitboard::new(&self.data).has_move()
+    }
+
+    /// Returns true if any gems are lined up in a match.
+    pub fn has_matches(&self) -> bool {
+        Bitboard::new(&self.data).has_matches()
+    }
+
+    /// Returns true if there are no moves left and no shuffles to get more with, or a column has iced all the way to
+    /// the top.
+    pub fn is_game_over(&self) -> bool {
+        (!self.is_valid() && self.shuffles_left == 0) || self.is_frozen()
+    }
+
+    /// Returns true if a column has iced all the way to the top (in gamemodes with ice).
+    pub fn is_frozen(&self) -> bool {
+        self.ice.is_full()
     }
 
     /// Returns true if you can make a move on a spot.
@@ -562,6 +795,76 @@ impl Board {
         }
     }
 
+    /// Gets every move that makes a match (or uses a hypercube), as a gem and the direction to swap it in.
+    /// Each swap only shows up once, since swapping a gem right is the same as swapping its neighbor left.
+    pub fn valid_moves(&self) -> Vec<(Point<usize>, Direction)> {
+        let bitboard = Bitboard::new(&self.data);
+        let masks = [Direction::Right, Direction::Down].map(|d| (d, bitboard.moves(d)));
+        let mut moves: Vec<_> = (0..self.data.len())
+            .flat_map(|i| masks.map(|(d, mask)| (i, d, mask)))
+            .filter(|&(i, _, mask)| mask & (1 << i) != 0)
+            .map(|(i, d, _)| (self.index_to_point(i), d))
+            .collect();
+        // Hypercubes can be swapped with any gem next to them, whether that lines anything up or not
+        let width = self.get_width();
+        for i in 0..self.data.len() {
+            if !matches!(self.data[i], Gem::Hypercube(_)) {
+                continue;
+            }
+            let cube = self.index_to_point(i);
+            let Point(x, y) = cube;
+            let swaps = [
+                (x + 1 < width).then_some((cube, Direction::Right)),
+                (y + 1 < width).then_some((cube, Direction::Down)),
+                (x > 0).then(|| (Point(x - 1, y), Direction::Right)),
+                (y > 0).then(|| (Point(x, y - 1), Direction::Down)),
+            ];
+            for (from, direction) in swaps.into_iter().flatten() {
+                let to = self.get_destination_from(from, &direction);
+                let neighbor = if from == cube { to } else { from };
+                if self.get_gem(neighbor) != Gem::Empty && !moves.contains(&(from, direction)) {
+                    moves.push((from, direction));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Gets how many points a move would score from its first clear (not counting cascades that follow).
+    pub fn move_points(&self, point: Point<usize>, direction: Direction) -> u32 {
+        let mut board = self.clone();
+        board.cursor = point;
+        board.swap(direction);
+        board.update_matching_gems();
+        board.score - self.score
+    }
+
+    /// Finds the move that scores the most points. If there's a tie, one of the best moves is picked at random, using
+    /// the board's own RNG so boards with the same seed give the same hint.
+    pub fn best_move(&mut self) -> Option<(Point<usize>, Direction)> {
+        let moves: Vec<_> = self
+            .valid_moves()
+            .into_iter()
+            .map(|(point, direction)| (point, direction, self.move_points(point, direction)))
+            .collect();
+        let best = moves.iter().map(|&(_, _, points)| points).max()?;
+        let best_moves: Vec<_> = moves
+            .into_iter()
+            .filter(|&(_, _, points)| points == best)
+            .map(|(point, direction, _)| (point, direction))
+            .collect();
+        best_moves.choose(&mut self.rng).copied()
+    }
+
+    /// Finds the best move for a hint, taking away points for it if the gamemode has a hint penalty.
+    pub fn take_hint(&mut self) -> Option<(Point<usize>, Direction)> {
+        let best = self.best_move();
+        if best.is_some() {
+            self.score = self.score.saturating_sub(self.config.hint_penalty);
+        }
+        best
+    }
+
     /// Returns true if you can swap a gem, given the gem and direction of swappage.
     pub fn is_valid_move(&self, point: Point<usize>, direction: Direction) -> bool {
         // Ensure that we aren't subtracting from a (0,0)
@@ -666,6 +969,20 @@ impl Board {
         self.get_destination_from(self.cursor, direction)
     }
 
+    /// Gets the gem the cursor was last swapped with, if that's on the board.
+    fn swap_destination(&self) -> Option<Point<usize>> {
+        let at_edge = match self.direction {
+            Direction::Left => self.cursor.0 == 0,
+            Direction::Up => self.cursor.1 == 0,
+            _ => false,
+        };
+        if at_edge {
+            None
+        } else {
+            Some(self.get_destination(&self.direction)).filter(|&p| self.is_in_board(p))
+        }
+    }
+
     /// Gets the destination of a swap, given a point and direction
     pub fn get_destination_from(&self, from: Point<usize>, direction: &Direction) -> Point<usize> {
         match direction {
@@ -1,3 +1,6 @@
+/// Cells along each side of the board. Boards are square, and every cell has to fit in a bitboard's u64.
+pub const BOARD_SIZE: usize = 8;
+
 /// Specifies how much points you get for each gem successfully swapped.
 pub const POINTS_SWAP: u8 = 30;
 
@@ -8,3 +11,18 @@ pub const PROGRESS_SWAP_INITIAL: f32 = 0.025;
 pub const PROGRESS_SWAP_FALLOFF: f32 = 0.9;
 
 pub const PROGRESS_SWAP_MIN: f32 = 0.001;
+
+/// How many shuffles to try before giving up on finding one with a move and no matches.
+pub const SHUFFLE_ATTEMPTS: u32 = 1000;
+
+/// Fewest moves Zen refills try to leave on the board.
+pub const ZEN_MIN_MOVES: u8 = 3;
+
+/// Ice rises every this many moves (in gamemodes with ice).
+pub const ICE_MOVES_PER_RISE: u32 = 2;
+
+/// Points for each row of ice broken.
+pub const POINTS_ICE: u32 = 100;
+
+/// Most moves Zen refills try to leave on the board. Any more than this and the game plays itself.
+pub const ZEN_MAX_MOVES: u8 = 12;
@@ -0,0 +1,41 @@
+use crate::gems::{Gem, GemColor};
+use crate::levels::LevelStats;
+use crate::point::Point;
+use crate::poker::PokerHand;
+
+/// Things that happen on a board that a frontend might want to react to (e.g. by playing a sound).
+/// Boards queue these up as they happen; take them with `Board::take_events`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BoardEvent {
+    /// Two gems were swapped.
+    Swap,
+    /// A swap was attempted, but it wouldn't have made a match.
+    InvalidSwap,
+    /// Matches were cleared from the board. The first clear after a swap is step 0, the clear after that step 1, etc.
+    Cascade(u8),
+    /// A single match was cleared, at `at`, as part of cascade step `cascade`. Contains the points it was worth
+    /// (including any gems that were set off by it), and the color of the gems that were lined up (if they had one).
+    Matched {
+        at: Point<usize>,
+        points: u32,
+        cascade: u8,
+        color: Option<GemColor>,
+    },
+    /// A poker hand was filled and scored (in gamemodes with poker). Contains the points it was worth.
+    PokerHand(PokerHand, u32),
+    /// A vertical match broke some rows of ice in a column (in gamemodes with ice). Contains the points it was worth.
+    IceBroken {
+        column: usize,
+        rows: u8,
+        points: u32,
+    },
+    /// A special gem was activated.
+    Detonation(Gem),
+    /// The board was shuffled with one of the game's shuffles.
+    Shuffle,
+    /// A level was finished. Contains what happened during it. This comes right before the `LevelUp` for the next
+    /// level.
+    LevelComplete(LevelStats),
+    /// The board went up a level. Contains the new level.
+    LevelUp(u8),
+}
@@ -2,7 +2,7 @@ use rand::Rng;
 use rand::distributions::{Distribution, Standard};
 
 /// Types of gems to use.
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Gem {
     Empty,
     // Normal gems.
@@ -18,7 +18,7 @@ pub enum Gem {
 }
 
 /// Gem colors. These are not associated with any special abilities nor do they include special gems (e.g. hypercubes)
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum GemColor {
     Blue,
     White,
@@ -29,9 +29,21 @@ pub enum GemColor {
     Purple,
 }
 
+impl GemColor {
+    pub const ALL: [GemColor; 7] = [
+        GemColor::Blue,
+        GemColor::White,
+        GemColor::Red,
+        GemColor::Yellow,
+        GemColor::Green,
+        GemColor::Orange,
+        GemColor::Purple,
+    ];
+}
+
 /// Enum for different (general) ways of selecting gems on a board.
 /// I mean I could also like add something with a vec of points if I want I guess
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum GemSelector {
     Color(GemColor),
     All,
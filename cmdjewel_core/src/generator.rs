@@ -0,0 +1,94 @@
+// Makes new gems for the holes in a board, without leaning on luck.
+//
+// Filling holes at random and checking the result can take hundreds of tries to find a board with a move, and sometimes
+// never does. Instead, gems go in one at a time, each in a color that doesn't finish a line, and then a few of them get
+// recolored until the board has about as many moves as the gamemode wants. Every step is bounded, so a refill always
+// takes about the same time.
+
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::bitboard::Bitboard;
+use crate::gems::{Gem, GemColor, GemSelector};
+
+/// How many times to go over the new gems looking for ones to recolor.
+const PASSES: usize = 4;
+
+/// Fills every empty cell with a new gem. New gems never finish a line, so nothing matches that wasn't already going
+/// to. If it can, the board ends up with a number of moves in `moves`; if there should be a move but no recoloring
+/// makes one, a hypercube goes in one of the holes instead.
+pub fn fill<R: Rng + ?Sized>(
+    data: &[Gem; 64],
+    moves: RangeInclusive<u32>,
+    rng: &mut R,
+) -> [Gem; 64] {
+    let mut data = *data;
+    let mut holes: Vec<usize> = (0..data.len()).filter(|&i| data[i] == Gem::Empty).collect();
+    if holes.is_empty() {
+        return data;
+    }
+    let mut bitboard = Bitboard::new(&data);
+
+    // 1. Fill the holes with colors that don't finish a line. A cell can only be the end of 6 lines, so one of the 7
+    //    colors is always left.
+    for &i in &holes {
+        let color = *safe_colors(&bitboard, i)
+            .choose(rng)
+            .expect("a color that doesn't finish a line");
+        data[i] = Gem::Normal(color);
+        bitboard.set(i, data[i]);
+    }
+
+    // 2. Recolor new gems, keeping each change that doesn't take the number of moves further from what we want. Keeping
+    //    changes that leave it the same lets this get past boards where no single recolor gets any closer.
+    let mut count = bitboard.move_count();
+    for _ in 0..PASSES {
+        if moves.contains(&count) {
+            break;
+        }
+        holes.shuffle(rng);
+        for &i in &holes {
+            if moves.contains(&count) {
+                break;
+            }
+            let mut colors = safe_colors(&bitboard, i);
+            colors.shuffle(rng);
+            for color in colors {
+                if data[i] == Gem::Normal(color) {
+                    continue;
+                }
+                bitboard.set(i, Gem::Normal(color));
+                let new = bitboard.move_count();
+                if distance(&moves, new) <= distance(&moves, count) {
+                    data[i] = Gem::Normal(color);
+                    count = new;
+                    break;
+                }
+            }
+            // Put back whatever color it ended up with
+            bitboard.set(i, data[i]);
+        }
+    }
+
+    // 3. Still no moves? A hypercube can always be used.
+    if count == 0 && *moves.start() > 0 {
+        let i = *holes.choose(rng).expect("at least one hole");
+        data[i] = Gem::Hypercube(GemSelector::None);
+    }
+    data
+}
+
+/// Gets every color a new gem at `i` could be without finishing a line.
+fn safe_colors(bitboard: &Bitboard, i: usize) -> Vec<GemColor> {
+    GemColor::ALL
+        .into_iter()
+        .filter(|&color| bitboard.line_ends(color) & (1 << i) == 0)
+        .collect()
+}
+
+/// How far a number of moves is from being in the range.
+fn distance(moves: &RangeInclusive<u32>, count: u32) -> u32 {
+    moves.start().saturating_sub(count) + count.saturating_sub(*moves.end())
+}
@@ -0,0 +1,71 @@
+// Ice Storm: ice creeps up the columns of the board from the bottom as moves are made, and vertical matches break it
+// back down. Once any column is iced all the way to the top, the game's over.
+//
+// Ice sits on top of the gems and doesn't get in the way of them; it's only a count of how far up each column it's
+// got to. A vertical match in a column breaks as many rows of ice as there were gems in it.
+
+use rand::Rng;
+use rand::seq::index;
+
+use crate::constants::BOARD_SIZE;
+use crate::point::Point;
+
+/// Columns on the board
+pub const COLUMNS: usize = BOARD_SIZE;
+
+/// Rows on the board, and rows of ice it takes to fill a column
+pub const ROWS: usize = BOARD_SIZE;
+
+/// How far ice has got up each column.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Ice {
+    heights: [u8; COLUMNS],
+}
+
+impl Ice {
+    /// Makes ice with the given heights (e.g. from a saved game). Heights past the top of the board are cut down.
+    pub fn new(heights: [u8; COLUMNS]) -> Self {
+        Ice {
+            heights: heights.map(|h| h.min(ROWS as u8)),
+        }
+    }
+
+    /// Gets how many rows of ice there are in each column, from left to right.
+    pub fn heights(&self) -> [u8; COLUMNS] {
+        self.heights
+    }
+
+    /// Gets how many rows of ice there are in a column.
+    pub fn height(&self, column: usize) -> u8 {
+        self.heights[column]
+    }
+
+    /// Returns true if a cell is under ice.
+    pub fn covers(&self, point: Point<usize>) -> bool {
+        point.1 + self.heights[point.0] as usize >= ROWS
+    }
+
+    /// Returns true once any column has iced all the way to the top.
+    pub fn is_full(&self) -> bool {
+        self.heights.iter().any(|&h| h as usize >= ROWS)
+    }
+
+    /// Raises the ice by a row in some different columns, picked at random.
+    pub fn rise<R: Rng + ?Sized>(&mut self, columns: usize, rng: &mut R) {
+        for column in index::sample(rng, COLUMNS, columns.min(COLUMNS)) {
+            self.heights[column] = (self.heights[column] + 1).min(ROWS as u8);
+        }
+    }
+
+    /// Breaks up to `rows` rows of ice in a column. Returns how many were actually broken.
+    pub fn break_rows(&mut self, column: usize, rows: u8) -> u8 {
+        let broken = rows.min(self.heights[column]);
+        self.heights[column] -= broken;
+        broken
+    }
+}
+
+/// How many columns the ice rises in each time it rises, on a level. It gets faster every few levels.
+pub fn rising_columns(level: u8) -> usize {
+    (1 + level as usize / 3).min(COLUMNS)
+}
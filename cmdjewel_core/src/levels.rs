@@ -0,0 +1,132 @@
+// Keeps track of how each level is going, and what (if anything) a level asks for before it's over.
+//
+// Most gamemodes go up a level once enough gems have been cleared. Gamemodes can also set goals for each level instead,
+// like clearing some gems of a color or making a few star gems, and the level's over once every goal has been met.
+
+use std::time::Duration;
+
+use crate::gems::{Gem, GemColor};
+
+/// Kinds of special gems
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Special {
+    Flame,
+    Star,
+    Hypercube,
+    Supernova,
+}
+
+impl Special {
+    pub const ALL: [Special; 4] = [
+        Special::Flame,
+        Special::Star,
+        Special::Hypercube,
+        Special::Supernova,
+    ];
+
+    /// Gets what kind of special gem a gem is, if it's special.
+    pub fn of(gem: Gem) -> Option<Special> {
+        match gem {
+            Gem::Flame(_) => Some(Special::Flame),
+            Gem::Star(_) => Some(Special::Star),
+            Gem::Hypercube(_) => Some(Special::Hypercube),
+            Gem::Supernova(_) => Some(Special::Supernova),
+            _ => None,
+        }
+    }
+}
+
+/// What happened during a level.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LevelStats {
+    /// Points scored (not counting points taken away for hints and shuffles)
+    pub points: u32,
+    /// Most points scored by one move, including every cascade after it
+    pub best_move: u32,
+    /// Moves made
+    pub moves: u32,
+    /// Gems cleared, for each color (in the same order as `GemColor::ALL`)
+    pub cleared: [u32; 7],
+    /// Special gems made, for each kind (in the same order as `Special::ALL`)
+    pub made: [u32; 4],
+    /// How long the level's been played for. Boards don't keep time themselves, so this only goes up when the
+    /// frontend calls `Board::add_time`.
+    pub time: Duration,
+}
+
+impl LevelStats {
+    /// Gets how many gems of a color were cleared.
+    pub fn cleared(&self, color: GemColor) -> u32 {
+        self.cleared[color as usize]
+    }
+
+    /// Gets how many special gems of a kind were made.
+    pub fn made(&self, special: Special) -> u32 {
+        self.made[special as usize]
+    }
+
+    /// Gets how many special gems were made, of any kind.
+    pub fn made_total(&self) -> u32 {
+        self.made.iter().sum()
+    }
+}
+
+/// Something a level asks for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Goal {
+    /// Clear some gems of a color
+    Clear(GemColor, u32),
+    /// Make some special gems of a kind
+    Make(Special, u32),
+    /// Score some points
+    Score(u32),
+}
+
+impl Goal {
+    /// Gets how much there is to do.
+    pub fn target(&self) -> u32 {
+        match *self {
+            Goal::Clear(_, n) | Goal::Make(_, n) | Goal::Score(n) => n,
+        }
+    }
+
+    /// Gets how much has been done so far, up to the target.
+    pub fn done(&self, stats: &LevelStats) -> u32 {
+        let done = match *self {
+            Goal::Clear(color, _) => stats.cleared(color),
+            Goal::Make(special, _) => stats.made(special),
+            Goal::Score(_) => stats.points,
+        };
+        done.min(self.target())
+    }
+
+    /// Returns true once the goal has been met.
+    pub fn is_met(&self, stats: &LevelStats) -> bool {
+        self.done(stats) >= self.target()
+    }
+}
+
+/// Gets how far along a level's goals are, between 0 and 1. Each goal counts the same, no matter how big it is.
+pub fn goal_progress(goals: &[Goal], stats: &LevelStats) -> f32 {
+    if goals.is_empty() {
+        return 1.0;
+    }
+    goals
+        .iter()
+        .map(|g| g.done(stats) as f32 / g.target().max(1) as f32)
+        .sum::<f32>()
+        / goals.len() as f32
+}
+
+/// Goals Zen can be played with, in place of its progress bar: a color to clear, and something else that changes from level to level. Each level asks for a bit
+/// more than the last.
+pub fn zen_goals(level: u8) -> Vec<Goal> {
+    let level = level as u32;
+    let color = GemColor::ALL[level as usize % GemColor::ALL.len()];
+    let other = match level % 3 {
+        0 => Goal::Make(Special::Flame, 1 + level.min(12) / 2),
+        1 => Goal::Score(1500 + 500 * level),
+        _ => Goal::Make(Special::Star, 1 + level.min(12) / 4),
+    };
+    vec![Goal::Clear(color, 15 + 5 * level.min(17)), other]
+}
@@ -1,5 +1,12 @@
+pub mod bitboard;
 pub mod board;
 mod constants;
+pub mod events;
 pub mod gems;
+pub mod generator;
+pub mod ice;
+pub mod levels;
 pub mod matches;
+pub mod notation;
 pub mod point;
+pub mod poker;
@@ -1,5 +1,10 @@
-use crate::{board::Board, gems::Gem, point::Point};
+use crate::{
+    board::Board,
+    gems::{Gem, GemSelector},
+    point::Point,
+};
 
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Match {
     /// A list of matched gems -- gems to remove from the board, or iterate to find a special gem in need of activation.
     pub gems: Vec<Point<usize>>,
@@ -47,6 +52,12 @@ pub fn scan_matches(board: &Board, width: usize, height: usize, is_vertical: boo
                     }
                     matched = vec![point];
                 }
+            } else {
+                // Empty spaces and hypercubes break lines up
+                if matched.len() > 2 {
+                    matches.push(Match::new(matched.clone()));
+                }
+                matched.clear();
             }
         }
         if matched.len() > 2 {
@@ -55,3 +66,55 @@ pub fn scan_matches(board: &Board, width: usize, height: usize, is_vertical: boo
     }
     return matches;
 }
+
+/// Combines lines of matching gems (from `scan_matches`) that cross each other into single matches, so L, T, and cross
+/// shapes (and anything bigger) each come out as one match. Gems where lines cross only show up once.
+///
+/// Also works out which special gem each match makes: a supernova for 6 or more in a line, a hypercube for 5, a star
+/// where lines cross, and a flame gem for 4. Matches go where the lines cross, or at their first gem.
+pub fn combine_lines(board: &Board, lines: Vec<Match>) -> Vec<Match> {
+    // Every line joins the groups it crosses, which can join groups that didn't cross each other before
+    let mut groups: Vec<Vec<Match>> = vec![];
+    for line in lines {
+        let (crossed, rest): (Vec<_>, Vec<_>) = groups.into_iter().partition(|group| {
+            group
+                .iter()
+                .any(|m| m.gems.iter().any(|g| line.gems.contains(g)))
+        });
+        let mut group: Vec<Match> = crossed.into_iter().flatten().collect();
+        group.push(line);
+        groups = rest;
+        groups.push(group);
+    }
+    groups
+        .into_iter()
+        .map(|group| {
+            let longest = group.iter().map(|m| m.gems.len()).max().unwrap_or(0);
+            let mut gems: Vec<Point<usize>> = vec![];
+            let mut crossing = None;
+            for &gem in group.iter().flat_map(|m| m.gems.iter()) {
+                if gems.contains(&gem) {
+                    crossing.get_or_insert(gem);
+                } else {
+                    gems.push(gem);
+                }
+            }
+            let what =
+                board
+                    .color_at_point(board.as_ref(), gems[0])
+                    .and_then(|color| match longest {
+                        6.. => Some(Gem::Supernova(color)),
+                        5 => Some(Gem::Hypercube(GemSelector::None)),
+                        _ if crossing.is_some() => Some(Gem::Star(color)),
+                        4 => Some(Gem::Flame(color)),
+                        _ => None,
+                    });
+            Match {
+                at: crossing.unwrap_or(gems[0]),
+                gems,
+                what,
+                children: vec![],
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,124 @@
+// A short way of writing boards down as text, for loading boards by hand (and for testing).
+//
+// Each gem is written as the first letter of its color: lowercase for normal gems, and uppercase for flame gems.
+// Star and supernova gems are a `*` or `+` followed by their color, and hypercubes are a `?` (followed by `=` and a
+// color if they've picked a color to take out, or `!` if they'll take out everything). Empty spaces are a `.`.
+// Rows are separated by `/`, top row first, so a board looks something like `bwrygopb/.../BwrygoP?`.
+
+use crate::gems::{Gem, GemColor, GemSelector};
+
+/// Reads a color out of its letter (in either case).
+fn parse_color(c: char) -> Option<GemColor> {
+    match c.to_ascii_lowercase() {
+        'b' => Some(GemColor::Blue),
+        'w' => Some(GemColor::White),
+        'r' => Some(GemColor::Red),
+        'y' => Some(GemColor::Yellow),
+        'g' => Some(GemColor::Green),
+        'o' => Some(GemColor::Orange),
+        'p' => Some(GemColor::Purple),
+        _ => None,
+    }
+}
+
+/// Gets the (lowercase) letter for a color.
+fn color_char(color: GemColor) -> char {
+    match color {
+        GemColor::Blue => 'b',
+        GemColor::White => 'w',
+        GemColor::Red => 'r',
+        GemColor::Yellow => 'y',
+        GemColor::Green => 'g',
+        GemColor::Orange => 'o',
+        GemColor::Purple => 'p',
+    }
+}
+
+/// Reads one gem from the front of `chars`, if there's a valid one there.
+fn next_gem(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Gem, String> {
+    let c = chars.next().ok_or("expected a gem")?;
+    let color = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        chars
+            .next()
+            .and_then(parse_color)
+            .ok_or(format!("expected a color after '{}'", c))
+    };
+    match c {
+        '.' => Ok(Gem::Empty),
+        '*' => Ok(Gem::Star(color(chars)?)),
+        '+' => Ok(Gem::Supernova(color(chars)?)),
+        '?' => match chars.peek() {
+            Some('!') => {
+                chars.next();
+                Ok(Gem::Hypercube(GemSelector::All))
+            }
+            Some('=') => {
+                chars.next();
+                Ok(Gem::Hypercube(GemSelector::Color(color(chars)?)))
+            }
+            _ => Ok(Gem::Hypercube(GemSelector::None)),
+        },
+        c => match parse_color(c) {
+            Some(color) if c.is_ascii_uppercase() => Ok(Gem::Flame(color)),
+            Some(color) => Ok(Gem::Normal(color)),
+            None => Err(format!("'{}' isn't a gem", c)),
+        },
+    }
+}
+
+/// Reads a single gem, like `r`, `*b`, or `?`.
+pub fn parse_gem(s: &str) -> Result<Gem, String> {
+    let mut chars = s.chars().peekable();
+    let gem = next_gem(&mut chars)?;
+    match chars.next() {
+        None => Ok(gem),
+        Some(_) => Err(format!("\"{}\" is more than one gem", s)),
+    }
+}
+
+/// Reads a whole board. Whitespace is ignored, and every row has to be full.
+pub fn parse(s: &str) -> Result<[Gem; 64], String> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let rows: Vec<&str> = s.split('/').collect();
+    if rows.len() != 8 {
+        return Err(format!("expected 8 rows, but there are {}", rows.len()));
+    }
+    let mut data = [Gem::Empty; 64];
+    for (y, row) in rows.iter().enumerate() {
+        let mut chars = row.chars().peekable();
+        let mut x = 0;
+        while chars.peek().is_some() {
+            if x == 8 {
+                return Err(format!("row {} has more than 8 gems", y + 1));
+            }
+            data[y * 8 + x] = next_gem(&mut chars)?;
+            x += 1;
+        }
+        if x < 8 {
+            return Err(format!("row {} only has {} gems", y + 1, x));
+        }
+    }
+    Ok(data)
+}
+
+/// Writes a single gem.
+pub fn write_gem(gem: Gem) -> String {
+    match gem {
+        Gem::Empty => ".".into(),
+        Gem::Normal(color) => color_char(color).to_string(),
+        Gem::Flame(color) => color_char(color).to_ascii_uppercase().to_string(),
+        Gem::Star(color) => format!("*{}", color_char(color)),
+        Gem::Supernova(color) => format!("+{}", color_char(color)),
+        Gem::Hypercube(GemSelector::None) => "?".into(),
+        Gem::Hypercube(GemSelector::All) => "?!".into(),
+        Gem::Hypercube(GemSelector::Color(color)) => format!("?={}", color_char(color)),
+    }
+}
+
+/// Writes a whole board, so that `parse` reads it back the same.
+pub fn write(data: &[Gem]) -> String {
+    data.chunks(8)
+        .map(|row| row.iter().map(|&gem| write_gem(gem)).collect::<String>())
+        .collect::<Vec<String>>()
+        .join("/")
+}
@@ -1,7 +1,7 @@
 use std::ops::{Add, Sub};
 
 /// Specifies a 2D x,y point
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Point<T>(pub T, pub T)
 where
     T: Add<Output = T> + Sub<Output = T> + PartialEq;
@@ -79,7 +79,7 @@ where
 }
 
 /// Specifies adjacent directions
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Direction {
     Left,
     Right,
@@ -0,0 +1,103 @@
+// Poker: every match deals the color it was into a hand of five, and full hands are scored like poker hands.
+//
+// Colors work like card ranks here. With 7 colors and 5 cards, every hand has at least a pair or all five colors
+// different (a spectrum), so there's always something to score.
+
+use crate::gems::GemColor;
+
+/// Cards in a full hand
+pub const HAND_SIZE: usize = 5;
+
+/// Kinds of poker hands, from worst to best.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum PokerHand {
+    /// Two of one color
+    Pair,
+    /// Two of one color and two of another
+    TwoPair,
+    /// Three of one color
+    ThreeOfAKind,
+    /// Five different colors
+    Spectrum,
+    /// Three of one color and two of another
+    FullHouse,
+    /// Four of one color
+    FourOfAKind,
+    /// Five of one color
+    Flush,
+}
+
+impl PokerHand {
+    pub const ALL: [PokerHand; 7] = [
+        PokerHand::Pair,
+        PokerHand::TwoPair,
+        PokerHand::ThreeOfAKind,
+        PokerHand::Spectrum,
+        PokerHand::FullHouse,
+        PokerHand::FourOfAKind,
+        PokerHand::Flush,
+    ];
+
+    /// Works out what kind of hand some cards are. Returns None if there aren't exactly 5 of them.
+    pub fn of(cards: &[GemColor]) -> Option<PokerHand> {
+        if cards.len() != HAND_SIZE {
+            return None;
+        }
+        // How many of each color there are, most first
+        let mut counts = [0; 7];
+        cards.iter().for_each(|&c| counts[c as usize] += 1);
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        Some(match counts {
+            [5, ..] => PokerHand::Flush,
+            [4, ..] => PokerHand::FourOfAKind,
+            [3, 2, ..] => PokerHand::FullHouse,
+            [3, ..] => PokerHand::ThreeOfAKind,
+            [2, 2, ..] => PokerHand::TwoPair,
+            [2, ..] => PokerHand::Pair,
+            _ => PokerHand::Spectrum,
+        })
+    }
+
+    /// Gets how many points the hand is worth.
+    pub fn points(&self) -> u32 {
+        match self {
+            PokerHand::Pair => 250,
+            PokerHand::TwoPair => 500,
+            PokerHand::ThreeOfAKind => 1000,
+            PokerHand::Spectrum => 1500,
+            PokerHand::FullHouse => 2500,
+            PokerHand::FourOfAKind => 5000,
+            PokerHand::Flush => 10000,
+        }
+    }
+}
+
+/// A hand being dealt, one match at a time.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Hand {
+    cards: Vec<GemColor>,
+}
+
+impl Hand {
+    /// Makes a hand out of cards that have already been dealt (e.g. from a saved game). Only the first 5 are kept.
+    pub fn new(cards: &[GemColor]) -> Self {
+        Hand {
+            cards: cards.iter().copied().take(HAND_SIZE).collect(),
+        }
+    }
+
+    /// Gets the cards dealt so far.
+    pub fn cards(&self) -> &[GemColor] {
+        &self.cards
+    }
+
+    /// Deals a card. If that fills the hand, it's scored and emptied out, and what it was is returned.
+    pub fn deal(&mut self, card: GemColor) -> Option<PokerHand> {
+        self.cards.push(card);
+        let hand = PokerHand::of(&self.cards);
+        if hand.is_some() {
+            self.cards.clear();
+        }
+        hand
+    }
+}
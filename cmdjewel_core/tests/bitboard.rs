@@ -0,0 +1,113 @@
+// Checks that bitboards agree with the slow way of looking at boards, one gem at a time.
+
+mod common;
+
+use cmdjewel_core::bitboard::Bitboard;
+use cmdjewel_core::gems::{Gem, GemColor, GemSelector};
+use cmdjewel_core::matches::scan_matches;
+use cmdjewel_core::point::{Direction, Point};
+use common::{colored_gem, from_data};
+use proptest::prelude::*;
+
+/// Any board, including holes and hypercubes.
+fn any_board() -> impl Strategy<Value = [Gem; 64]> {
+    prop::collection::vec(
+        prop_oneof![
+            20 => colored_gem(),
+            1 => Just(Gem::Hypercube(GemSelector::None)),
+            2 => Just(Gem::Empty),
+        ],
+        64,
+    )
+    .prop_map(|gems| gems.try_into().unwrap())
+}
+
+/// A full board with nothing lined up yet, like the ones you make moves on.
+fn settled_board() -> impl Strategy<Value = [Gem; 64]> {
+    prop::collection::vec(colored_gem(), 64).prop_map(|gems| {
+        let mut data: [Gem; 64] = gems.try_into().unwrap();
+        for i in 0..64 {
+            // Recolor anything that finishes a line to the left or above. Only 2 colors can finish one, so this
+            // always stops.
+            while makes_line(&data, i) {
+                data[i] = recolor(data[i]);
+            }
+        }
+        data
+    })
+}
+
+fn color(gem: Gem) -> Option<GemColor> {
+    match gem {
+        Gem::Normal(c) | Gem::Flame(c) | Gem::Star(c) | Gem::Supernova(c) => Some(c),
+        _ => None,
+    }
+}
+
+fn makes_line(data: &[Gem; 64], i: usize) -> bool {
+    let (x, y) = (i % 8, i / 8);
+    let same = |j: usize| color(data[j]) == color(data[i]);
+    (x >= 2 && same(i - 1) && same(i - 2)) || (y >= 2 && same(i - 8) && same(i - 16))
+}
+
+fn recolor(gem: Gem) -> Gem {
+    let all = GemColor::ALL;
+    let next = |c: GemColor| all[(all.iter().position(|&o| o == c).unwrap() + 1) % all.len()];
+    match gem {
+        Gem::Normal(c) => Gem::Normal(next(c)),
+        Gem::Flame(c) => Gem::Flame(next(c)),
+        Gem::Star(c) => Gem::Star(next(c)),
+        Gem::Supernova(c) => Gem::Supernova(next(c)),
+        other => other,
+    }
+}
+
+/// Turns a mask into a sorted list of points.
+fn points(mask: u64) -> Vec<(usize, usize)> {
+    (0..64)
+        .filter(|i| mask & (1 << i) != 0)
+        .map(|i| (i % 8, i / 8))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn lines_are_the_gems_in_matches(data in any_board()) {
+        let board = from_data(data);
+        let mut expected: Vec<_> = [true, false]
+            .into_iter()
+            .flat_map(|vertical| scan_matches(&board, 8, 8, vertical))
+            .flat_map(|m| m.gems)
+            .map(|p| (p.0, p.1))
+            .collect();
+        expected.sort_by_key(|&(x, y)| (y, x));
+        expected.dedup();
+        prop_assert_eq!(points(Bitboard::new(&data).lines()), expected);
+    }
+
+    #[test]
+    fn moves_are_the_swaps_that_make_matches(data in settled_board()) {
+        let board = from_data(data);
+        let bitboard = Bitboard::new(&data);
+        for direction in [Direction::Left, Direction::Right, Direction::Up, Direction::Down] {
+            let expected: Vec<_> = (0..64)
+                .map(|i| (i % 8, i / 8))
+                .filter(|&(x, y)| board.is_valid_move(Point(x, y), direction))
+                .collect();
+            prop_assert_eq!(points(bitboard.moves(direction)), expected);
+        }
+    }
+
+    #[test]
+    fn has_move_agrees_with_checking_every_gem(data in settled_board(), cube in 0..128usize) {
+        let mut data = data;
+        // Sometimes there's a hypercube, which can always be used
+        if cube < 64 {
+            data[cube] = Gem::Hypercube(GemSelector::None);
+        }
+        let board = from_data(data);
+        let expected = (0..64).any(|i| board.is_valid_gem(Point(i % 8, i / 8)));
+        prop_assert_eq!(Bitboard::new(&data).has_move(), expected);
+        prop_assert_eq!(board.is_valid(), expected);
+    }
+}
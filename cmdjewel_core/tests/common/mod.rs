@@ -0,0 +1,48 @@
+// Fixtures shared by the tests: boards written out with `notation`, and strategies for making random gems.
+// Each test file only uses some of these.
+#![allow(dead_code)]
+
+use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::gems::{Gem, GemColor, GemSelector};
+use cmdjewel_core::notation;
+use proptest::prelude::*;
+
+/// Makes a classic board out of 8 rows of gems, one per line, top row first.
+pub fn board(rows: &str) -> Board {
+    board_with(BoardConfig::new_classic(), rows)
+}
+
+/// Makes a board for any gamemode out of 8 rows of gems, one per line, top row first.
+pub fn board_with(config: BoardConfig, rows: &str) -> Board {
+    let rows: Vec<&str> = rows.split_whitespace().collect();
+    let data = notation::parse(&rows.join("/")).expect("test boards should be valid");
+    Board::new_controlled(config, data, 0, 0, 0.0)
+}
+
+/// Makes a classic board out of gems laid out row by row.
+pub fn from_data(data: [Gem; 64]) -> Board {
+    Board::new_controlled(BoardConfig::new_classic(), data, 0, 0, 0.0)
+}
+
+/// Any gem color.
+pub fn color() -> impl Strategy<Value = GemColor> {
+    prop::sample::select(GemColor::ALL.to_vec())
+}
+
+/// Any gem with a color, mostly normal ones.
+pub fn colored_gem() -> impl Strategy<Value = Gem> {
+    prop_oneof![
+        20 => color().prop_map(Gem::Normal),
+        1 => color().prop_map(Gem::Flame),
+        1 => color().prop_map(Gem::Star),
+        1 => color().prop_map(Gem::Supernova),
+    ]
+}
+
+/// Any gem that can be on a board before anything's been swapped, mostly normal ones.
+pub fn gem() -> impl Strategy<Value = Gem> {
+    prop_oneof![
+        23 => colored_gem(),
+        1 => Just(Gem::Hypercube(GemSelector::None)),
+    ]
+}
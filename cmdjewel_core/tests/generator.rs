@@ -0,0 +1,109 @@
+// Checks that refills keep what was already on the board, never line up new gems, and leave moves when they should.
+// Boards get the same checks, since that's where new games and refills come from.
+
+mod common;
+
+use cmdjewel_core::bitboard::Bitboard;
+use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::gems::Gem;
+use cmdjewel_core::generator;
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// A board after gravity: each column has gems at the bottom and holes on top.
+fn fallen_board() -> impl Strategy<Value = [Gem; 64]> {
+    let column = (0..=8usize, prop::collection::vec(common::color(), 8));
+    prop::collection::vec(column, 8).prop_map(|columns| {
+        let mut data = [Gem::Empty; 64];
+        for (x, (height, colors)) in columns.into_iter().enumerate() {
+            for y in 8 - height..8 {
+                data[y * 8 + x] = Gem::Normal(colors[y]);
+            }
+        }
+        data
+    })
+}
+
+/// Drops new gems into a board until it's full.
+fn refill(board: &mut Board) {
+    board.fill_gem_buffer();
+    for _ in 0..board.get_width() {
+        board.slide_down();
+    }
+}
+
+fn new_board(config: BoardConfig, seed: u64) -> Board {
+    let mut board = Board::new(config);
+    board.reseed(seed);
+    refill(&mut board);
+    board
+}
+
+#[test]
+fn new_boards_can_start_with_free_matches() {
+    let config = BoardConfig {
+        no_free_matches: false,
+        ..BoardConfig::new_classic()
+    };
+    assert!((0..100).any(|seed| new_board(config.clone(), seed).has_matches()));
+}
+
+proptest! {
+    #[test]
+    fn new_boards_start_without_matches(seed: u64) {
+        for config in [BoardConfig::new_classic(), BoardConfig::new_zen()] {
+            let board = new_board(config, seed);
+            prop_assert!(board.is_full());
+            prop_assert!(!board.has_matches());
+        }
+    }
+
+    #[test]
+    fn board_refills_dont_match_by_themselves(data in fallen_board(), seed: u64) {
+        let mut board = Board::new_controlled(BoardConfig::new_classic(), data, 0, 0, 0.0);
+        board.reseed(seed);
+        refill(&mut board);
+        prop_assert!(board.is_full());
+        prop_assert_eq!(Bitboard::new(board.as_ref()).lines(), Bitboard::new(&data).lines());
+    }
+
+    #[test]
+    fn refills_fill_every_hole_and_keep_every_gem(data in fallen_board(), seed: u64) {
+        let filled = generator::fill(&data, 1..=u32::MAX, &mut StdRng::seed_from_u64(seed));
+        for i in 0..64 {
+            prop_assert_ne!(filled[i], Gem::Empty);
+            if data[i] != Gem::Empty {
+                prop_assert_eq!(filled[i], data[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn new_gems_never_finish_a_line(data in fallen_board(), seed: u64) {
+        let filled = generator::fill(&data, 1..=u32::MAX, &mut StdRng::seed_from_u64(seed));
+        // Only lines that were already there are left
+        prop_assert_eq!(Bitboard::new(&filled).lines(), Bitboard::new(&data).lines());
+    }
+
+    #[test]
+    fn refills_leave_a_move(data in fallen_board(), seed: u64) {
+        prop_assume!(data.contains(&Gem::Empty));
+        let filled = generator::fill(&data, 1..=u32::MAX, &mut StdRng::seed_from_u64(seed));
+        prop_assert!(Bitboard::new(&filled).has_move());
+    }
+
+    #[test]
+    fn refills_only_add_hypercubes_when_asked_for_a_move(data in fallen_board(), seed: u64) {
+        let filled = generator::fill(&data, 0..=u32::MAX, &mut StdRng::seed_from_u64(seed));
+        prop_assert!(!filled.contains(&Gem::Empty));
+        prop_assert!(filled.iter().all(|&g| matches!(g, Gem::Normal(_))));
+    }
+
+    #[test]
+    fn new_boards_have_about_as_many_moves_as_asked_for(seed: u64, min in 1..8u32, extra in 0..8u32) {
+        let moves = min..=min + extra;
+        let filled = generator::fill(&[Gem::Empty; 64], moves.clone(), &mut StdRng::seed_from_u64(seed));
+        prop_assert!(moves.contains(&Bitboard::new(&filled).move_count()));
+    }
+}
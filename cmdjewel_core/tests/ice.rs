@@ -0,0 +1,87 @@
+// Tests for Ice Storm: ice rising with moves, vertical matches breaking it, and the game ending once it reaches the top.
+
+use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::events::BoardEvent;
+use cmdjewel_core::ice::{self, Ice};
+use cmdjewel_core::notation;
+use cmdjewel_core::point::{Direction, Point};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+#[test]
+fn ice_rises_in_different_columns() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut ice = Ice::default();
+    ice.rise(3, &mut rng);
+    assert_eq!(ice.heights().iter().filter(|&&h| h == 1).count(), 3);
+    assert_eq!(ice.heights().iter().sum::<u8>(), 3);
+    // Ice never goes past the top
+    for _ in 0..20 {
+        ice.rise(ice::COLUMNS, &mut rng);
+    }
+    assert_eq!(ice.heights(), [ice::ROWS as u8; ice::COLUMNS]);
+    assert!(ice.is_full());
+}
+
+#[test]
+fn ice_covers_the_bottom_of_its_column() {
+    let ice = Ice::new([2, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(ice.covers(Point(0, 7)));
+    assert!(ice.covers(Point(0, 6)));
+    assert!(!ice.covers(Point(0, 5)));
+    assert!(!ice.covers(Point(1, 7)));
+}
+
+#[test]
+fn moves_raise_the_ice() {
+    let data =
+        notation::parse("brbbwgwg/......../......../......../......../......../......../........")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_ice_storm(), data, 0, 0, 0.0);
+    // Make the same move twice, putting the gems back in between
+    for _ in 0..2 {
+        assert!(board.try_swap(Direction::Right));
+        board.swap(Direction::Right);
+    }
+    assert_eq!(board.get_ice().heights().iter().sum::<u8>(), 1);
+}
+
+#[test]
+fn vertical_matches_break_ice() {
+    let data =
+        notation::parse("......../......../......../......../......../..r...../..r...../rrr.....")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_ice_storm(), data, 0, 0, 0.0);
+    board.set_ice(Ice::new([2, 0, 4, 0, 0, 0, 0, 0]));
+    board.update_matching_gems();
+    // Only the vertical line in column 2 breaks anything, and only as many rows as it had gems
+    assert_eq!(board.get_ice().heights(), [2, 0, 1, 0, 0, 0, 0, 0]);
+    assert!(board.take_events().contains(&BoardEvent::IceBroken {
+        column: 2,
+        rows: 3,
+        points: 300
+    }));
+}
+
+#[test]
+fn frozen_columns_end_the_game() {
+    let data =
+        notation::parse("rbrbrbrb/brbrbrbr/rbrbrbrb/brbrbrbr/rbrbrbrb/brbrbrbr/rbrbrbrb/brbrbrbr")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_ice_storm(), data, 0, 0, 0.0);
+    assert!(!board.is_game_over());
+    board.set_ice(Ice::new([0, 0, 0, 8, 0, 0, 0, 0]));
+    assert!(board.is_frozen());
+    assert!(board.is_game_over());
+}
+
+#[test]
+fn other_gamemodes_dont_break_ice() {
+    let data =
+        notation::parse("r......./r......./r......./......../......../......../......../........")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_classic(), data, 0, 0, 0.0);
+    board.set_ice(Ice::new([3, 0, 0, 0, 0, 0, 0, 0]));
+    board.update_matching_gems();
+    assert_eq!(board.get_ice().height(0), 3);
+}
@@ -0,0 +1,125 @@
+// Tests for level stats and goals: what gets counted when gems are cleared, and when levels with goals end.
+
+mod common;
+
+use cmdjewel_core::board::BoardConfig;
+use cmdjewel_core::events::BoardEvent;
+use cmdjewel_core::gems::GemColor;
+use cmdjewel_core::levels::{self, Goal, LevelStats, Special};
+use cmdjewel_core::point::Point;
+use common::board_with;
+
+fn clear_three_reds(_: u8) -> Vec<Goal> {
+    vec![Goal::Clear(GemColor::Red, 3)]
+}
+
+#[test]
+fn clears_are_counted_by_color_and_kind() {
+    let mut b = board_with(
+        BoardConfig::new_classic(),
+        "
+        ........
+        ........
+        ........
+        rrrr....
+        ........
+        bbb.....
+        ........
+        ........
+        ",
+    );
+    b.set_cursor(Point(7, 7));
+    b.update_matching_gems();
+    let stats = b.get_level_stats();
+    assert_eq!(stats.cleared(GemColor::Red), 4);
+    assert_eq!(stats.cleared(GemColor::Blue), 3);
+    assert_eq!(stats.made(Special::Flame), 1);
+    assert_eq!(stats.points, b.get_score());
+    assert_eq!(stats.best_move, b.get_score());
+}
+
+#[test]
+fn levels_with_goals_end_once_the_goals_are_met() {
+    let config = BoardConfig {
+        goals: Some(clear_three_reds),
+        ..BoardConfig::new_classic()
+    };
+    let mut b = board_with(
+        config,
+        "
+        ........
+        ........
+        ........
+        rr......
+        ........
+        ........
+        ........
+        rrr.....
+        ",
+    );
+    b.update_level();
+    assert_eq!(b.get_level(), 0);
+    assert_eq!(b.get_level_progress(), 0.0);
+
+    b.update_matching_gems();
+    b.take_events();
+    b.update_level();
+    assert_eq!(b.get_level(), 1);
+    let events = b.take_events();
+    let Some(BoardEvent::LevelComplete(stats)) = events.first() else {
+        panic!("expected the level to be complete, got {:?}", events);
+    };
+    assert_eq!(stats.cleared(GemColor::Red), 3);
+    assert_eq!(events.get(1), Some(&BoardEvent::LevelUp(1)));
+    // The next level starts from nothing
+    assert_eq!(b.get_level_stats(), &LevelStats::default());
+}
+
+#[test]
+fn best_moves_start_over_each_level() {
+    let config = BoardConfig {
+        goals: Some(clear_three_reds),
+        ..BoardConfig::new_classic()
+    };
+    // Clearing the reds finishes the level, and drops the greens into a match that belongs to the next one
+    let mut b = board_with(
+        config,
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        .gg.....
+        rrrg....
+        ",
+    );
+    b.update_matching_gems();
+    b.update_level();
+    assert_eq!(b.get_level(), 1);
+    b.slide_down();
+    b.update_matching_gems();
+    let stats = b.get_level_stats();
+    assert!(stats.points > 0);
+    assert_eq!(stats.best_move, stats.points);
+}
+
+#[test]
+fn goal_progress_counts_each_goal_the_same() {
+    let goals = [Goal::Clear(GemColor::Red, 10), Goal::Score(100)];
+    let mut stats = LevelStats::default();
+    stats.cleared[GemColor::Red as usize] = 20;
+    assert_eq!(levels::goal_progress(&goals, &stats), 0.5);
+    stats.points = 50;
+    assert_eq!(levels::goal_progress(&goals, &stats), 0.75);
+}
+
+#[test]
+fn every_zen_level_has_goals_that_can_be_met() {
+    for level in 0..=u8::MAX {
+        let goals = levels::zen_goals(level);
+        assert!(!goals.is_empty());
+        assert!(goals.iter().all(|g| g.target() > 0));
+    }
+}
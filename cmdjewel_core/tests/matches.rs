@@ -0,0 +1,486 @@
+// Golden tests for match detection: boards are written out with `notation`, and what gets matched (and what special
+// gems come out of it) is checked by hand.
+
+mod common;
+
+use cmdjewel_core::gems::{Gem, GemColor, GemSelector};
+use cmdjewel_core::matches::Match;
+use cmdjewel_core::point::{Direction, Point};
+use common::board;
+
+/// Gets the gems in a match as (x, y) pairs, sorted so they're easy to compare.
+fn gems(m: &Match) -> Vec<(usize, usize)> {
+    let mut gems: Vec<_> = m.gems.iter().map(|p| (p.0, p.1)).collect();
+    gems.sort();
+    gems
+}
+
+#[test]
+fn three_in_a_row() {
+    let b = board(
+        "
+        rrr.....
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(gems(&matches[0]), vec![(0, 0), (1, 0), (2, 0)]);
+    assert_eq!(matches[0].what, None);
+}
+
+#[test]
+fn three_in_a_column() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        .....g..
+        .....g..
+        .....g..
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(gems(&matches[0]), vec![(5, 5), (5, 6), (5, 7)]);
+}
+
+#[test]
+fn four_makes_a_flame_gem() {
+    let b = board(
+        "
+        ........
+        ........
+        bbbb....
+        ........
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].what, Some(Gem::Flame(GemColor::Blue)));
+}
+
+#[test]
+fn five_makes_a_hypercube() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ...wwwww
+        ........
+        ........
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].what, Some(Gem::Hypercube(GemSelector::None)));
+}
+
+#[test]
+fn six_makes_a_supernova() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        pppppp..
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].what, Some(Gem::Supernova(GemColor::Purple)));
+}
+
+#[test]
+fn l_shape_makes_a_star() {
+    let mut b = board(
+        "
+        r.......
+        r.......
+        rrr.....
+        ........
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    // Out of the way, so the star goes where the lines cross
+    b.set_cursor(Point(7, 7));
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(
+        gems(&matches[0]),
+        vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)]
+    );
+    assert_eq!(matches[0].what, Some(Gem::Star(GemColor::Red)));
+    assert_eq!(matches[0].at, Point(0, 2));
+}
+
+#[test]
+fn t_shape_makes_a_star() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ...yyy..
+        ....y...
+        ....y...
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(
+        gems(&matches[0]),
+        vec![(3, 4), (4, 4), (4, 5), (4, 6), (5, 4)]
+    );
+    assert_eq!(matches[0].what, Some(Gem::Star(GemColor::Yellow)));
+    assert_eq!(matches[0].at, Point(4, 4));
+}
+
+#[test]
+fn cross_makes_a_star() {
+    let b = board(
+        "
+        ........
+        ........
+        ......o.
+        .....ooo
+        ......o.
+        ........
+        ........
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(gems(&matches[0]).len(), 5);
+    assert_eq!(matches[0].what, Some(Gem::Star(GemColor::Orange)));
+    assert_eq!(matches[0].at, Point(6, 3));
+}
+
+#[test]
+fn a_line_crossing_two_lines_joins_them() {
+    // Both columns are found before the row that joins them
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        .r.r....
+        .rrr....
+        .r.r....
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(gems(&matches[0]).len(), 7);
+    assert_eq!(matches[0].what, Some(Gem::Star(GemColor::Red)));
+}
+
+#[test]
+fn two_lines_crossing_one_line_are_joined() {
+    // The column is found before both rows
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ....ggg.
+        ....g...
+        ....ggg.
+        ........
+        ........
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(gems(&matches[0]).len(), 7);
+}
+
+#[test]
+fn long_lines_beat_crossings() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        bbbbb...
+        ..b.....
+        ..b.....
+        ",
+    );
+    let matches = b.get_matches();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].what, Some(Gem::Hypercube(GemSelector::None)));
+}
+
+#[test]
+fn separate_matches_stay_separate() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        rrr.bbb.
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    assert_eq!(b.get_matches().len(), 2);
+}
+
+#[test]
+fn lines_touching_without_crossing_stay_separate() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        rrr.....
+        ...r....
+        ...r....
+        ...r....
+        ",
+    );
+    assert_eq!(b.get_matches().len(), 2);
+}
+
+#[test]
+fn gaps_break_lines() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        rr.r....
+        ........
+        bb?b....
+        ........
+        ",
+    );
+    assert!(b.get_matches().is_empty());
+    assert!(!b.has_matches());
+}
+
+#[test]
+fn special_gems_go_where_the_swap_was() {
+    let mut b = board(
+        "
+        ........
+        ........
+        ........
+        rrrr....
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    b.set_cursor(Point(2, 3));
+    assert_eq!(b.get_matches()[0].at, Point(2, 3));
+}
+
+#[test]
+fn matching_along_the_left_edge() {
+    // The cursor starts in the top left, where the last swap direction points off the board
+    let b = board(
+        "
+        r.......
+        r.......
+        r.......
+        ........
+        ........
+        ........
+        ........
+        ........
+        ",
+    );
+    assert_eq!(b.get_matches()[0].at, Point(0, 0));
+}
+
+#[test]
+fn flame_gems_set_off_stars() {
+    let mut b = board(
+        "
+        ........
+        ........
+        ..rRr...
+        ...*bb..g
+        ........
+        ......p.
+        ...y....
+        ........
+        ",
+    );
+    b.update_matching_gems();
+    // The flame gem takes out the star under it, which takes out its row and column
+    assert_eq!(b.get_gem(Point(7, 3)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(3, 6)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(6, 5)), Gem::Normal(GemColor::Purple));
+}
+
+#[test]
+fn hypercubes_set_off_by_other_gems_take_their_color() {
+    let mut b = board(
+        "
+        ........
+        ........
+        ..rRr...
+        ...?....
+        ........
+        .......r
+        .......b
+        ........
+        ",
+    );
+    b.update_matching_gems();
+    assert_eq!(b.get_gem(Point(7, 5)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(7, 6)), Gem::Normal(GemColor::Blue));
+}
+
+#[test]
+fn swapped_hypercubes_take_out_a_color() {
+    let mut b = board(
+        "
+        ........
+        ...?=g....
+        ...g....
+        ....g...
+        .....b..
+        ........
+        ........
+        .......g
+        ",
+    );
+    b.update_matching_gems();
+    assert!(
+        b.as_ref()
+            .iter()
+            .all(|&g| g != Gem::Normal(GemColor::Green))
+    );
+    assert_eq!(b.get_gem(Point(3, 1)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(5, 4)), Gem::Normal(GemColor::Blue));
+}
+
+#[test]
+fn supernovas_take_out_three_rows_and_columns() {
+    let mut b = board(
+        "
+        ........
+        ........
+        ........
+        .rr+rr...
+        .......g
+        .......g
+        ........
+        ...g..g.
+        ",
+    );
+    b.set_cursor(Point(7, 7));
+    b.update_matching_gems();
+    assert_eq!(b.get_gem(Point(7, 4)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(7, 5)), Gem::Normal(GemColor::Green));
+    assert_eq!(b.get_gem(Point(3, 7)), Gem::Empty);
+    assert_eq!(b.get_gem(Point(6, 7)), Gem::Normal(GemColor::Green));
+}
+
+#[test]
+fn new_special_gems_survive_what_made_them() {
+    let mut b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        rRrr....
+        ",
+    );
+    b.update_matching_gems();
+    // The flame gem blows up the gem next to it, which is where the new flame gem goes
+    assert_eq!(b.get_gem(Point(0, 7)), Gem::Flame(GemColor::Red));
+}
+
+#[test]
+fn special_gems_only_go_off_once() {
+    let b = board(
+        "
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        ........
+        rRR.....
+        ",
+    );
+    let matches = b.get_matches();
+    // Both flame gems are in the match, so neither one sets the other off again
+    assert_eq!(matches[0].children.len(), 2);
+    assert!(matches[0].children.iter().all(|c| c.children.is_empty()));
+}
+
+#[test]
+fn hypercubes_are_hinted_when_theyre_the_only_move() {
+    let mut b = board(
+        "
+        bwrygopb
+        ygopbwry
+        pbwrygop
+        rygopbwr
+        opbw?ygo
+        wrygopbw
+        gopbwryg
+        bwrygopb
+        ",
+    );
+    assert!(b.is_valid());
+    let mut moves = b.valid_moves();
+    moves.sort_by_key(|&(p, d)| (p.1, p.0, d == Direction::Down));
+    assert_eq!(
+        moves,
+        vec![
+            (Point(4, 3), Direction::Down),
+            (Point(3, 4), Direction::Right),
+            (Point(4, 4), Direction::Right),
+            (Point(4, 4), Direction::Down),
+        ]
+    );
+    let (point, direction) = b.take_hint().expect("the hypercube should be hinted");
+    assert!(moves.contains(&(point, direction)));
+}
@@ -0,0 +1,71 @@
+// Tests for poker hands: what each hand counts as, and matches dealing into them.
+
+use cmdjewel_core::board::{Board, BoardConfig};
+use cmdjewel_core::events::BoardEvent;
+use cmdjewel_core::gems::GemColor::{self, *};
+use cmdjewel_core::notation;
+use cmdjewel_core::poker::{Hand, PokerHand};
+
+#[test]
+fn hands_are_ranked_like_poker() {
+    let cases: [([GemColor; 5], PokerHand); 7] = [
+        ([Red, Red, Blue, Green, White], PokerHand::Pair),
+        ([Red, Red, Blue, Blue, White], PokerHand::TwoPair),
+        ([Red, Blue, Red, Green, Red], PokerHand::ThreeOfAKind),
+        ([Red, Blue, Green, White, Purple], PokerHand::Spectrum),
+        ([Red, Blue, Red, Blue, Red], PokerHand::FullHouse),
+        (
+            [Yellow, Yellow, Orange, Yellow, Yellow],
+            PokerHand::FourOfAKind,
+        ),
+        ([Orange; 5], PokerHand::Flush),
+    ];
+    for (cards, hand) in cases {
+        assert_eq!(PokerHand::of(&cards), Some(hand), "{:?}", cards);
+    }
+}
+
+#[test]
+fn better_hands_are_worth_more() {
+    let points = PokerHand::ALL.map(|h| h.points());
+    assert!(points.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn hands_only_score_once_theyre_full() {
+    let mut hand = Hand::default();
+    for color in [Red, Red, Blue, Blue] {
+        assert_eq!(hand.deal(color), None);
+    }
+    assert_eq!(hand.cards(), &[Red, Red, Blue, Blue]);
+    assert_eq!(hand.deal(Red), Some(PokerHand::FullHouse));
+    assert!(hand.cards().is_empty());
+}
+
+#[test]
+fn matches_deal_their_color() {
+    let data =
+        notation::parse("......../......../......../rrr...../......../bbb...../......../........")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_poker(), data, 0, 0, 0.0);
+    board.set_hand(Hand::new(&[Red, Red, Blue]));
+    board.update_matching_gems();
+    let score = 6 * 30;
+    // Red and blue fill the hand: a full house
+    assert!(board.get_hand().cards().is_empty());
+    assert_eq!(board.get_score(), score + PokerHand::FullHouse.points());
+    assert!(board.take_events().contains(&BoardEvent::PokerHand(
+        PokerHand::FullHouse,
+        PokerHand::FullHouse.points()
+    )));
+}
+
+#[test]
+fn other_gamemodes_dont_deal() {
+    let data =
+        notation::parse("rrr...../......../......../......../......../......../......../........")
+            .unwrap();
+    let mut board = Board::new_controlled(BoardConfig::new_classic(), data, 0, 0, 0.0);
+    board.update_matching_gems();
+    assert!(board.get_hand().cards().is_empty());
+}
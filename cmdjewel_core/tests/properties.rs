@@ -0,0 +1,148 @@
+// Property tests: things that should hold for any board, checked against lots of random ones.
+
+mod common;
+
+use cmdjewel_core::board::Board;
+use cmdjewel_core::gems::Gem;
+use cmdjewel_core::notation;
+use common::gem;
+use proptest::prelude::*;
+
+/// A full board.
+fn full_board() -> impl Strategy<Value = [Gem; 64]> {
+    prop::collection::vec(gem(), 64).prop_map(|gems| gems.try_into().unwrap())
+}
+
+/// A board with holes in it.
+fn board_with_holes() -> impl Strategy<Value = [Gem; 64]> {
+    prop::collection::vec(prop_oneof![3 => gem(), 1 => Just(Gem::Empty)], 64)
+        .prop_map(|gems| gems.try_into().unwrap())
+}
+
+fn board(data: [Gem; 64], seed: u64) -> Board {
+    let mut board = common::from_data(data);
+    board.reseed(seed);
+    board
+}
+
+/// Clears matches and drops new gems in until there's nothing left to do, like the game does between moves.
+fn resolve(board: &mut Board) {
+    for _ in 0..10_000 {
+        if board.is_buffer_empty() {
+            if !board.is_full() {
+                board.fill_gem_buffer();
+            } else if board.get_matches().is_empty() {
+                return;
+            } else {
+                board.update_matching_gems();
+            }
+        }
+        board.slide_down();
+    }
+    panic!(
+        "the board never settled:\n{}",
+        notation::write(board.as_ref())
+    );
+}
+
+proptest! {
+    #[test]
+    fn resolved_boards_have_no_matches(data in full_board(), seed: u64) {
+        let mut board = board(data, seed);
+        resolve(&mut board);
+        prop_assert!(board.is_full());
+        prop_assert!(board.get_matches().is_empty());
+        prop_assert!(!board.has_matches());
+    }
+
+    #[test]
+    fn matches_never_list_a_gem_twice(data in full_board()) {
+        let board = board(data, 0);
+        for m in board.get_matches() {
+            let mut gems: Vec<_> = m.gems.iter().map(|p| (p.0, p.1)).collect();
+            gems.sort();
+            gems.dedup();
+            prop_assert_eq!(gems.len(), m.gems.len());
+        }
+    }
+
+    #[test]
+    fn gravity_keeps_every_gem(data in board_with_holes()) {
+        let mut board = board(data, 0);
+        // Nothing's in the buffer, so nothing new comes in
+        for _ in 0..board.get_width() {
+            board.slide_down();
+        }
+        for x in 0..8 {
+            let column = |data: &[Gem]| -> Vec<Gem> {
+                (0..8).map(|y| data[y * 8 + x]).filter(|&g| g != Gem::Empty).collect()
+            };
+            // Same gems, in the same order...
+            prop_assert_eq!(column(&data), column(board.as_ref()));
+            // ...all at the bottom
+            let gems = column(board.as_ref()).len();
+            prop_assert!((0..8 - gems).all(|y| board.as_ref()[y * 8 + x] == Gem::Empty));
+        }
+    }
+
+    #[test]
+    fn score_never_goes_down(data in full_board(), seed: u64, moves in prop::collection::vec(any::<usize>(), 1..10)) {
+        let mut board = board(data, seed);
+        resolve(&mut board);
+        for i in moves {
+            let valid = board.valid_moves();
+            if valid.is_empty() {
+                break;
+            }
+            let score = board.get_score();
+            let (point, direction) = valid[i % valid.len()];
+            board.set_cursor(point);
+            prop_assert!(board.try_swap(direction));
+            resolve(&mut board);
+            prop_assert!(board.get_score() > score);
+        }
+    }
+
+    #[test]
+    fn shuffles_leave_a_move_and_no_matches(data in full_board(), seed: u64) {
+        let mut board = board(data, seed);
+        if board.shuffle().is_some() {
+            prop_assert!(board.is_valid());
+            prop_assert!(!board.has_matches());
+        } else {
+            prop_assert_eq!(board.as_ref(), &data[..]);
+        }
+    }
+
+    #[test]
+    fn regenerated_boards_have_a_move_and_no_matches(data in full_board(), seed: u64) {
+        let mut board = board(data, seed);
+        board.regenerate();
+        for _ in 0..board.get_width() {
+            board.slide_down();
+        }
+        prop_assert!(board.is_full());
+        prop_assert!(board.is_valid());
+        prop_assert!(!board.has_matches());
+    }
+
+    #[test]
+    fn hints_are_the_same_for_the_same_seed(data in full_board(), seed: u64) {
+        let mut a = board(data, seed);
+        let mut b = board(data, seed);
+        for _ in 0..3 {
+            prop_assert_eq!(a.best_move(), b.best_move());
+        }
+    }
+
+    #[test]
+    fn boards_with_a_move_always_have_a_hint(data in full_board(), seed: u64) {
+        let mut board = board(data, seed);
+        prop_assert_eq!(board.is_valid(), board.best_move().is_some());
+    }
+
+    #[test]
+    fn notation_reads_back_what_it_writes(data in board_with_holes()) {
+        prop_assert_eq!(notation::parse(&notation::write(&data)), Ok(data));
+    }
+}